@@ -0,0 +1,266 @@
+use crate::board::{Board, Move};
+use crate::config::Config;
+use crate::engine::{Engine, SearchResult, MATE_SCORE, MATE_THRESHOLD, MAX_DEPTH, TB_WIN_THRESHOLD};
+use crate::game::Game;
+use crate::human::{Input, MoveInput};
+use crate::i18n::{tr, Message};
+use crate::notation;
+use crate::pieces::{Color, PieceVariant};
+use crate::syzygy::Tablebases;
+use crate::tt::TranspositionTable;
+use crate::ui::Ui;
+use crate::user_input::{self, KeyPress};
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+// how often new results of the search are looked for while waiting for a key
+const ANALYSIS_TICK: Duration = Duration::from_millis(100);
+/// number of lines shown if nothing else is configured
+pub const DEFAULT_LINES: usize = 3;
+
+/// what the analysis shows beside the board
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct AnalysisView {
+    /// the depth of the last complete iteration
+    pub depth: u8,
+    /// the evaluation of the best line in centipawns from the perspective of white
+    pub score: i32,
+    /// the best lines with their scores, e.g. `+0.35  1. e4 e5 2. Nf3`
+    pub lines: Vec<String>,
+    /// the plies until the next capture or pawn move if the tablebases decide the position, positive if the side
+    /// to move wins
+    pub tablebase: Option<i32>,
+}
+
+impl AnalysisView {
+    /// the share of the evaluation bar filled for white, one half for an equal position
+    pub fn white_share(&self) -> f64 {
+        if self.score > TB_WIN_THRESHOLD {
+            1.0
+        } else if self.score < -TB_WIN_THRESHOLD {
+            0.0
+        } else {
+            1.0 / (1.0 + 10f64.powf(-self.score as f64 / 400.0))
+        }
+    }
+
+    /// the depth and the evaluation, e.g. `Depth 12  +0.35`, followed by the verdict of the tablebases in moves of
+    /// the side to move if they know the position
+    pub fn heading(&self) -> String {
+        let depth = tr(Message::AnalysisDepth).replace("{}", &self.depth.to_string());
+        let heading = format!("{depth}  {}", format_score(self.score));
+        let Some(dtz) = self.tablebase else {
+            return heading;
+        };
+        let verdict = if dtz > 0 { Message::AnalysisTablebaseWin } else { Message::AnalysisTablebaseLoss };
+        format!("{heading}  {}", tr(verdict).replace("{}", &((dtz.abs() + 1) / 2).to_string()))
+    }
+}
+
+/// a score from the perspective of white in pawns, e.g. `+0.35`, the moves until a king is captured, e.g. `#-2`,
+/// or `+TB`/`-TB` for a position the tablebases call won or lost
+pub fn format_score(score: i32) -> String {
+    if score > MATE_THRESHOLD {
+        format!("#{}", (MATE_SCORE - score + 1) / 2)
+    } else if score < -MATE_THRESHOLD {
+        format!("#-{}", (MATE_SCORE + score + 1) / 2)
+    } else if score > TB_WIN_THRESHOLD {
+        "+TB".to_string()
+    } else if score < -TB_WIN_THRESHOLD {
+        "-TB".to_string()
+    } else {
+        format!("{:+.2}", score as f64 / 100.0)
+    }
+}
+
+/// the engine searching one position on its own thread until it is stopped or dropped
+struct Analyser {
+    board: Board,
+    color: Color,
+    fullmove_number: u32,
+    stop: Arc<AtomicBool>,
+    search: Option<JoinHandle<()>>,
+    results: Receiver<SearchResult>,
+}
+
+impl Analyser {
+    /// starts searching the current position of the game, the transposition table is kept between positions
+    fn start(game: &Game, tt: Arc<TranspositionTable>, tablebases: Option<Arc<Tablebases>>, config: &Config) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let mut engine = Engine::with_table(MAX_DEPTH, tt, stop.clone());
+        engine.threads = config.engine_threads;
+        engine.multi_pv = config.analysis_lines;
+        engine.tablebases = tablebases;
+        let (sender, results) = mpsc::channel();
+        let (board, color) = (game.board.clone(), game.side_to_move);
+        let search = thread::spawn(move || {
+            engine.search_with_info(&board, color, None, &mut |result| {
+                let _ = sender.send(result.clone());
+            });
+        });
+        Analyser {
+            board: game.board.clone(),
+            color,
+            fullmove_number: game.fullmove_number,
+            stop,
+            search: Some(search),
+            results,
+        }
+    }
+
+    /// the result of the deepest iteration finished since the last call
+    fn latest(&self) -> Option<AnalysisView> {
+        let result = self.results.try_iter().last()?;
+        let perspective = if self.color == Color::White { 1 } else { -1 };
+        let lines = result
+            .lines
+            .iter()
+            .map(|line| {
+                let pv = notation::line_to_san(&self.board, self.color, self.fullmove_number, &line.pv);
+                format!("{:>6}  {pv}", format_score(line.score * perspective))
+            })
+            .collect();
+        Some(AnalysisView { depth: result.depth, score: result.score * perspective, lines, tablebase: result.tablebase })
+    }
+}
+
+impl Drop for Analyser {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(search) = self.search.take() {
+            let _ = search.join();
+        }
+    }
+}
+
+/// the analysis mode, started with `rusty-chess analyse`: the player at this terminal moves the pieces of both
+/// sides, steps back and forth through the moves and edits the position, while the engine searches the displayed one
+pub fn run(start: &Game, tablebases: Option<Arc<Tablebases>>, config: &Config, ui: &mut Ui) {
+    let tt = Arc::new(TranspositionTable::new(config.engine_hash));
+    let bindings = ui.settings.key_bindings.clone();
+    let keys = [
+        tr(Message::AnalysisKeys).replace(
+            "{}",
+            &format!("{}/{}", bindings.describe(KeyPress::StepBack), bindings.describe(KeyPress::StepForward)),
+        ),
+        tr(Message::AnalysisEditKey).replace("{}", &bindings.describe(KeyPress::EditSquare)),
+        tr(Message::AnalysisSideKey).replace("{}", &bindings.describe(KeyPress::SwitchSide)),
+    ]
+    .join(", ");
+    // an edited position becomes the new start, the moves entered before are dropped
+    let mut start = replay(start, &[]);
+    // all moves entered, the ones after the displayed position can be stepped forward to again
+    let mut moves: Vec<Move> = vec![];
+    let mut current = 0;
+    let mut input = MoveInput::default();
+
+    let mut game = replay(&start, &[]);
+    let mut analyser = None;
+    let mut winner = None;
+    let mut changed = true;
+    ui.init(&game.board);
+    loop {
+        if changed {
+            changed = false;
+            // the old search is stopped before the next one starts
+            analyser = None;
+            input.reset(ui);
+            game = replay(&start, &moves[..current]);
+            winner = [Color::White, Color::Black].into_iter().find(|color| game.board.king_position(color.opponent()).is_none());
+            match winner {
+                Some(color) => {
+                    ui.set_analysis(None);
+                    ui.set_message(Some(tr(Message::PlayerWon).replace("{}", &color.to_string())));
+                }
+                None => {
+                    analyser = Some(Analyser::start(&game, tt.clone(), tablebases.clone(), config));
+                    ui.set_message(Some(keys.clone()));
+                }
+            }
+            ui.redraw(&game.board);
+        }
+        if let Some(view) = analyser.as_ref().and_then(Analyser::latest) {
+            ui.set_analysis(Some(view));
+        }
+
+        let Some(pressed_key) = user_input::poll_user_input(&bindings, ANALYSIS_TICK) else {
+            continue;
+        };
+        changed = match input.handle(pressed_key, &game.board, game.side_to_move, ui) {
+            // the game is over once a king has been captured, only stepping back is possible
+            Input::Move(_) if winner.is_some() => false,
+            Input::Move(mv) => {
+                moves.truncate(current);
+                moves.push(mv);
+                current += 1;
+                true
+            }
+            Input::Key(KeyPress::StepBack) if current > 0 => {
+                current -= 1;
+                true
+            }
+            Input::Key(KeyPress::StepForward) if current < moves.len() => {
+                current += 1;
+                true
+            }
+            Input::Key(KeyPress::EditSquare) => {
+                let square = ui.cursor_pos.into();
+                let mut board = game.board.clone();
+                let piece = board[square].map(|p| (p.variant, p.color));
+                board[square] = None;
+                if let Some((variant, color)) = next_piece(piece, square.row) {
+                    board.place(variant, color, square);
+                }
+                start = edited(&board, game.side_to_move, game.fullmove_number);
+                moves.clear();
+                current = 0;
+                true
+            }
+            Input::Key(KeyPress::SwitchSide) => {
+                start = edited(&game.board, game.side_to_move.opponent(), game.fullmove_number);
+                moves.clear();
+                current = 0;
+                true
+            }
+            Input::Key(KeyPress::Esc) => return,
+            _ => false,
+        };
+    }
+}
+
+/// the game after playing the moves from its start position
+fn replay(start: &Game, moves: &[Move]) -> Game {
+    let mut game = Game::from_fen(&start.start_fen, None).expect("the start position has been exported as FEN");
+    for mv in moves {
+        let _ = game.play(*mv);
+    }
+    game
+}
+
+/// the order in which editing a square cycles through the pieces, ending with the empty square
+const EDIT_ORDER: [PieceVariant; 6] =
+    [PieceVariant::Pawn, PieceVariant::Knight, PieceVariant::Bishop, PieceVariant::Rook, PieceVariant::Queen, PieceVariant::King];
+
+/// the piece replacing the given one on a square of the row: the white pieces, then the black ones, then none,
+/// pawns are skipped on the first and the last row
+fn next_piece(piece: Option<(PieceVariant, Color)>, row: i8) -> Option<(PieceVariant, Color)> {
+    let order: Vec<(PieceVariant, Color)> = [Color::White, Color::Black]
+        .into_iter()
+        .flat_map(|color| EDIT_ORDER.into_iter().map(move |variant| (variant, color)))
+        .filter(|(variant, _)| *variant != PieceVariant::Pawn || (1..7).contains(&row))
+        .collect();
+    match piece.and_then(|piece| order.iter().position(|p| *p == piece)) {
+        Some(index) => order.get(index + 1).copied(),
+        None if piece.is_some() => None,
+        None => order.first().copied(),
+    }
+}
+
+/// a game starting from an edited position
+fn edited(board: &Board, side_to_move: Color, fullmove_number: u32) -> Game {
+    Game::from_fen(&board.to_fen(side_to_move, fullmove_number), None).expect("a board exports a valid FEN")
+}
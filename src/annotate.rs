@@ -0,0 +1,306 @@
+use crate::analysis::format_score;
+use crate::config::Config;
+use crate::engine::Engine;
+use crate::game::{Game, GameResult};
+use crate::i18n::{self, tr, Message};
+use crate::notation::{self, parse_san, MoveNote};
+use crate::pieces::Color;
+
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+// a move losing at least this many centipawns against the best one is an inaccuracy, a mistake or a blunder
+const INACCURACY_LOSS: i32 = 50;
+const MISTAKE_LOSS: i32 = 100;
+const BLUNDER_LOSS: i32 = 300;
+// a captured king counts as this many centipawns, so missing one is a blunder but doesn't dwarf the other losses
+const SCORE_CAP: i32 = 1000;
+
+/// how good a move has been compared to the best move of the engine
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Classification {
+    Best,
+    Good,
+    Inaccuracy,
+    Mistake,
+    Blunder,
+}
+
+impl Classification {
+    fn from_loss(loss: i32) -> Self {
+        match loss {
+            0 => Classification::Best,
+            loss if loss < INACCURACY_LOSS => Classification::Good,
+            loss if loss < MISTAKE_LOSS => Classification::Inaccuracy,
+            loss if loss < BLUNDER_LOSS => Classification::Mistake,
+            _ => Classification::Blunder,
+        }
+    }
+
+    /// the name of the class in the language of the user
+    pub fn name(self) -> &'static str {
+        tr(match self {
+            Classification::Best => Message::AnnotationBest,
+            Classification::Good => Message::AnnotationGood,
+            Classification::Inaccuracy => Message::AnnotationInaccuracy,
+            Classification::Mistake => Message::AnnotationMistake,
+            Classification::Blunder => Message::AnnotationBlunder,
+        })
+    }
+
+    /// the numeric annotation glyph of PGN: `?!`, `?` and `??`
+    pub fn nag(self) -> Option<u8> {
+        match self {
+            Classification::Best | Classification::Good => None,
+            Classification::Inaccuracy => Some(6),
+            Classification::Mistake => Some(2),
+            Classification::Blunder => Some(4),
+        }
+    }
+}
+
+/// the verdict of the engine on a single move
+#[derive(Clone, Debug)]
+pub struct MoveAnalysis {
+    /// the side which played the move
+    pub color: Color,
+    pub classification: Classification,
+    /// centipawns lost against the best move
+    pub loss: i32,
+    /// the evaluation after the move and after the best move, both from the perspective of white
+    pub score: i32,
+    pub best_score: i32,
+    /// the best line of the engine in standard algebraic notation with move numbers
+    pub best_line: String,
+}
+
+/// the verdicts on all moves of a game and how accurately white and black have played
+pub struct GameAnalysis {
+    pub moves: Vec<MoveAnalysis>,
+    /// from 0 to 100 percent, None for a side without moves
+    pub accuracy: [Option<f64>; 2],
+}
+
+impl GameAnalysis {
+    /// a NAG and, for inaccuracies and worse, the better line as comment for every move
+    pub fn notes(&self) -> Vec<MoveNote> {
+        self.moves
+            .iter()
+            .map(|analysis| MoveNote {
+                nag: analysis.classification.nag(),
+                comment: analysis.classification.nag().map(|_| {
+                    let better = format!("{} ({})", analysis.best_line, format_score(analysis.best_score));
+                    format!(
+                        "{} ({}). {}",
+                        analysis.classification.name(),
+                        format_score(analysis.score),
+                        tr(Message::AnnotationBetter).replace("{}", &better)
+                    )
+                }),
+            })
+            .collect()
+    }
+
+    /// the accuracy of both sides as PGN tags
+    pub fn tags(&self) -> Vec<(&'static str, String)> {
+        [("WhiteAccuracy", self.accuracy[0]), ("BlackAccuracy", self.accuracy[1])]
+            .into_iter()
+            .filter_map(|(key, accuracy)| accuracy.map(|accuracy| (key, format!("{accuracy:.1}"))))
+            .collect()
+    }
+}
+
+/// searches every position of the game to the depth of the engine and compares each move with the best one,
+/// `progress` is called with the number of the position before it is searched and the number of all positions
+pub fn analyse_game(game: &Game, engine: &mut Engine, progress: &mut dyn FnMut(usize, usize)) -> GameAnalysis {
+    let mut replay = Game::from_fen(&game.start_fen, None).expect("the start position has been exported as FEN");
+    let mut positions = vec![];
+    let mut king_captured = false;
+    for i in 0..=game.moves.len() {
+        progress(i + 1, game.moves.len() + 1);
+        let color = replay.side_to_move;
+        // after a captured king there is nothing left to search, the side to move has lost
+        let (score, best_move, best_line) = if king_captured {
+            (-SCORE_CAP, None, String::new())
+        } else {
+            let result = engine.search(&replay.board, color, None);
+            let line = notation::line_to_san(&replay.board, color, replay.fullmove_number, &result.pv);
+            (result.score.clamp(-SCORE_CAP, SCORE_CAP), result.best_move, line)
+        };
+        // the scores from the perspective of white
+        let perspective = if color == Color::White { 1 } else { -1 };
+        positions.push((color, score * perspective, best_move, best_line));
+        if let Some(mv) = game.moves.get(i) {
+            king_captured = replay.play(*mv).is_err();
+        }
+    }
+
+    let mut moves = vec![];
+    let mut accuracies: [Vec<f64>; 2] = [vec![], vec![]];
+    for i in 0..game.moves.len() {
+        let (color, best_score, best_move, best_line) = &positions[i];
+        let score = positions[i + 1].1;
+        let perspective = if *color == Color::White { 1 } else { -1 };
+        // the deeper search after the best move may see a little more, the best move loses nothing
+        let loss = if *best_move == Some(game.moves[i]) { 0 } else { ((best_score - score) * perspective).max(0) };
+        accuracies[if *color == Color::White { 0 } else { 1 }].push(move_accuracy(best_score * perspective, score * perspective));
+        moves.push(MoveAnalysis {
+            color: *color,
+            classification: Classification::from_loss(loss),
+            loss,
+            score,
+            best_score: *best_score,
+            best_line: best_line.clone(),
+        });
+    }
+    let accuracy = accuracies.map(|values| (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64));
+    GameAnalysis { moves, accuracy }
+}
+
+/// the chance to win in percent for an evaluation in centipawns, following the model of lichess
+fn win_percent(score: i32) -> f64 {
+    50.0 + 50.0 * (2.0 / (1.0 + (-0.00368208 * score as f64).exp()) - 1.0)
+}
+
+/// the accuracy of a move in percent from the evaluations of the mover before and after it, following lichess
+fn move_accuracy(before: i32, after: i32) -> f64 {
+    let drop = (win_percent(before) - win_percent(after)).max(0.0);
+    (103.1668 * (-0.04354 * drop).exp() - 3.1669).clamp(0.0, 100.0)
+}
+
+/// an engine to the depth, hash size and threads of the config
+fn create_engine(config: &Config) -> Engine {
+    let mut engine = Engine::new(config.engine_depth, config.engine_hash);
+    engine.threads = config.engine_threads;
+    engine
+}
+
+/// analyses a game just played and saves it annotated into a new file in the current directory,
+/// returns the name of the file
+pub fn save_game(
+    game: &Game,
+    names: [String; 2],
+    result: GameResult,
+    config: &Config,
+    progress: &mut dyn FnMut(usize, usize),
+) -> io::Result<PathBuf> {
+    let analysis = analyse_game(game, &mut create_engine(config), progress);
+    let [white, black] = names;
+    let mut tags = vec![
+        ("Event", "rusty-chess".to_string()),
+        ("Site", "?".to_string()),
+        ("Date", notation::pgn_date()),
+        ("Round", "-".to_string()),
+        ("White", white),
+        ("Black", black),
+        ("Annotator", annotator(config)),
+    ];
+    tags.extend(analysis.tags());
+    let pgn = notation::write_annotated_pgn(game, &tags, result.pgn(), result.describe().as_deref(), &analysis.notes());
+
+    let date = notation::pgn_date().replace('.', "-");
+    let path = (1..)
+        .map(|n| PathBuf::from(if n == 1 { format!("rusty-chess-{date}.pgn") } else { format!("rusty-chess-{date}-{n}.pgn") }))
+        .find(|path| !path.exists())
+        .expect("one of the names is free");
+    fs::write(&path, pgn)?;
+    Ok(path)
+}
+
+fn annotator(config: &Config) -> String {
+    format!("rusty-chess (depth {})", config.engine_depth)
+}
+
+/// annotates all games of a PGN file, started with `rusty-chess annotate <pgn> [--output <file>] [options]`
+///
+/// the annotated games are written to the output file or to stdout, when writing to a file the accuracy
+/// of each game is printed as well
+pub fn run(args: &[String]) -> Result<(), String> {
+    let input = PathBuf::from(args.first().ok_or("expected the PGN file to annotate")?);
+    let mut output = None;
+    let mut config_args = vec![];
+    let mut args = args[1..].iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--output" => output = Some(PathBuf::from(args.next().ok_or("--output expects a path")?)),
+            _ => config_args.push(arg.clone()),
+        }
+    }
+    let config = Config::load(&config_args)?;
+    i18n::set_language(config.language);
+
+    let content = fs::read_to_string(&input).map_err(|e| format!("could not read {}: {e}", input.display()))?;
+    let games = notation::parse_pgn(&content);
+    if games.is_empty() {
+        return Err(format!("{} contains no games", input.display()));
+    }
+    let mut out: Box<dyn Write> = match &output {
+        Some(path) => Box::new(fs::File::create(path).map_err(|e| format!("could not create {}: {e}", path.display()))?),
+        None => Box::new(io::stdout()),
+    };
+
+    let mut engine = create_engine(&config);
+    for (number, pgn) in games.iter().enumerate() {
+        let fen = pgn.tag("FEN").map(str::to_string).unwrap_or(Game::new(None).start_fen);
+        let mut game = Game::from_fen(&fen, None)?;
+        // the game ends before the first move the rules of this game don't know, e.g. castling, its result is
+        // replaced by `*` as the game has not been played to the end
+        let mut truncated = None;
+        for san in &pgn.moves {
+            let Some(mv) = parse_san(&game.board, game.side_to_move, san) else {
+                let dots = if game.side_to_move == Color::White { "." } else { "..." };
+                let comment = tr(Message::SummaryTruncated).replace("{}", &format!("{}{dots} {san}", game.fullmove_number));
+                eprintln!("{}: {comment}", tr(Message::SummaryGame).replace("{}", &(number + 1).to_string()));
+                truncated = Some(comment);
+                break;
+            };
+            let _ = game.play(mv);
+        }
+
+        engine.clear();
+        let analysis = analyse_game(&game, &mut engine, &mut |_, _| ());
+        // the tags of the game are kept, the ones written anyway are left out
+        let mut tags: Vec<(&str, String)> = pgn
+            .tags
+            .iter()
+            .filter(|(key, _)| !matches!(key.as_str(), "Result" | "SetUp" | "FEN" | "Annotator" | "WhiteAccuracy" | "BlackAccuracy"))
+            .map(|(key, value)| (key.as_str(), value.clone()))
+            .collect();
+        tags.push(("Annotator", annotator(&config)));
+        tags.extend(analysis.tags());
+        let result = if truncated.is_some() { "*" } else { pgn.tag("Result").unwrap_or("*") };
+        let text = notation::write_annotated_pgn(&game, &tags, result, truncated.as_deref(), &analysis.notes());
+        out.write_all(text.as_bytes()).map_err(|e| format!("could not write the PGN: {e}"))?;
+
+        if output.is_some() {
+            print_summary(number + 1, pgn, &analysis);
+        }
+    }
+    Ok(())
+}
+
+/// the accuracy and the number of mistakes of both sides
+fn print_summary(number: usize, pgn: &notation::PgnGame, analysis: &GameAnalysis) {
+    println!(
+        "{}: {} - {}, {}",
+        tr(Message::SummaryGame).replace("{}", &number.to_string()),
+        pgn.tag("White").unwrap_or("?"),
+        pgn.tag("Black").unwrap_or("?"),
+        tr(Message::SummaryMoves).replace("{}", &analysis.moves.len().to_string())
+    );
+    for (index, color) in [Color::White, Color::Black].into_iter().enumerate() {
+        let count = |classification: Classification| {
+            let moves = analysis.moves.iter();
+            moves.filter(|verdict| verdict.color == color && verdict.classification == classification).count()
+        };
+        let accuracy = analysis.accuracy[index].map(|accuracy| format!("{accuracy:.1}%")).unwrap_or("-".to_string());
+        println!(
+            "  {color}: {}, {}, {}, {}",
+            tr(Message::SummaryAccuracy).replace("{}", &accuracy),
+            tr(Message::SummaryInaccuracies).replace("{}", &count(Classification::Inaccuracy).to_string()),
+            tr(Message::SummaryMistakes).replace("{}", &count(Classification::Mistake).to_string()),
+            tr(Message::SummaryBlunders).replace("{}", &count(Classification::Blunder).to_string())
+        );
+    }
+}
@@ -1,16 +1,65 @@
 use crate::pieces::Color::{Black, White};
 use crate::pieces::{Color, Piece, PieceVariant, Position};
-use std::any::Any;
 use std::fmt;
 use std::fmt::Formatter;
 use std::ops::{Index, IndexMut};
 
+// the pieces a pawn can be promoted to, the most valuable first
+const PROMOTIONS: [PieceVariant; 4] = [PieceVariant::Queen, PieceVariant::Rook, PieceVariant::Bishop, PieceVariant::Knight];
+
+/// signals that a move captured a king and thereby ended the game
+#[derive(Debug)]
+pub struct KingCaptured;
+
+/// a move of a piece from one square to another
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Move {
+    pub from: Position,
+    pub to: Position,
+    // the piece a pawn reaching the last rank turns into
+    pub promotion: Option<PieceVariant>,
+}
+
+impl Move {
+    pub fn new(from: Position, to: Position) -> Self {
+        Move { from, to, promotion: None }
+    }
+
+    /// the move of a piece onto a square, a pawn reaching the last rank is promoted to a queen
+    pub fn of_piece(piece: Piece, to: Position) -> Self {
+        Move {
+            from: piece.position,
+            to,
+            promotion: piece.promotes_on(to).then_some(PieceVariant::Queen),
+        }
+    }
+
+    /// parses a move in the long algebraic notation of UCI, e.g. `e2e4` or `e7e8q`
+    pub fn from_uci(notation: &str) -> Option<Move> {
+        let from = Position::from_square_name(notation.get(0..2)?)?;
+        let to = Position::from_square_name(notation.get(2..4)?)?;
+        let promotion = match notation.get(4..) {
+            None | Some("") => None,
+            Some(letter) => Some(PieceVariant::from_letter(letter.parse().ok()?)?),
+        };
+        Some(Move { from, to, promotion })
+    }
+
+    pub fn to_uci(self) -> String {
+        let promotion = self.promotion.map(|variant| variant.letter().to_ascii_lowercase());
+        format!("{}{}{}", self.from.to_square_name(), self.to.to_square_name(), promotion.map(String::from).unwrap_or_default())
+    }
+}
+
+#[derive(Default, Clone)]
 pub struct Board {
     // piece_store: Vec<Piece>,
     beaten_white_pieces: Vec<Piece>,
     beaten_black_pieces: Vec<Piece>,
     // to be used as [[rows]columns]
     pub board_array: [[Option<Piece>; 8]; 8],
+    // origin and destination of the most recent move
+    pub last_move: Option<(Position, Position)>,
 }
 
 impl Index<Position> for Board {
@@ -29,7 +78,7 @@ impl IndexMut<Position> for Board {
 
 impl Board {
     pub fn new() -> Self {
-        Board{ beaten_white_pieces: vec![], beaten_black_pieces: vec![], board_array: Default::default() }
+        Self::default()
     }
 
     // initializes a board with the default chess layout
@@ -42,16 +91,45 @@ impl Board {
 
     // clears the Board
     pub fn clear(&mut self) {
-        todo!("Do it without piece store")
-        // self.piece_store.clear();
+        *self = Board::default();
+    }
+
+    /// puts a new piece onto a square, e.g. to set up a position piece by piece after clear
+    pub fn place(&mut self, variant: PieceVariant, color: Color, position: Position) {
+        self[position] = Some(Piece::new(variant, position, color));
     }
 
-    // build a chessboard from an input string in chess notation
-    pub fn import_from_str() -> Self {
-        todo!()
+    /// builds a board from the piece placement, the first field of a position in Forsyth-Edwards Notation
+    pub fn from_fen(placement: &str) -> Result<Self, String> {
+        let mut board = Board::new();
+        let rows: Vec<&str> = placement.split('/').collect();
+        if rows.len() != 8 {
+            return Err(format!("expected 8 rows in '{placement}'"));
+        }
+        for (row, text) in rows.iter().enumerate() {
+            let mut col = 0;
+            for c in text.chars() {
+                if let Some(empty) = c.to_digit(10) {
+                    col += empty as usize;
+                    continue;
+                }
+                let variant = PieceVariant::from_letter(c).ok_or(format!("unknown piece '{c}' in '{placement}'"))?;
+                let color = if c.is_ascii_uppercase() { White } else { Black };
+                if col >= 8 {
+                    return Err(format!("expected 8 squares in row '{text}' of '{placement}'"));
+                }
+                board.place(variant, color, Position::new(col as i8, row as i8));
+                col += 1;
+            }
+            if col != 8 {
+                return Err(format!("expected 8 squares in row '{text}' of '{placement}'"));
+            }
+        }
+        Ok(board)
     }
 
-    pub fn update_field(&mut self, old_piece_pos: Position, new_piece_pos: Position) -> Result<(), ()> {
+    pub fn update_field(&mut self, old_piece_pos: Position, new_piece_pos: Position) -> Result<(), KingCaptured> {
+        self.last_move = Some((old_piece_pos, new_piece_pos));
         if let Some(p) = self[new_piece_pos].take() {
             if p.is_black() {
                 self.beaten_black_pieces.push(p);
@@ -59,12 +137,124 @@ impl Board {
                 self.beaten_white_pieces.push(p);
             }
             if p.variant == PieceVariant::King {
-                return Err(());
+                return Err(KingCaptured);
             }
         }
         self[new_piece_pos] = self[old_piece_pos].take();
         self[new_piece_pos].as_mut().unwrap().position = new_piece_pos; // we know there is a piece on this position. This was a nasty line of code (；′⌒`)
-        return Ok(());
+        Ok(())
+    }
+
+    /// plays a move and remembers it as the last move
+    pub fn play(&mut self, mv: Move) -> Result<(), KingCaptured> {
+        self.update_field(mv.from, mv.to)?;
+        if let (Some(variant), Some(p)) = (mv.promotion, self[mv.to].as_mut()) {
+            let _ = p.promote(variant);
+        }
+        Ok(())
+    }
+
+    /// moves a piece without any bookkeeping and returns the captured piece, used by the engine to try out moves
+    pub(crate) fn make_move(&mut self, mv: Move) -> Option<Piece> {
+        let captured = self[mv.to].take();
+        self[mv.to] = self[mv.from].take();
+        if let Some(p) = self[mv.to].as_mut() {
+            p.position = mv.to;
+            if let Some(variant) = mv.promotion {
+                p.variant = variant;
+            }
+        }
+        captured
+    }
+
+    /// takes back a move made with make_move
+    pub(crate) fn unmake_move(&mut self, mv: Move, captured: Option<Piece>) {
+        self[mv.from] = self[mv.to].take();
+        if let Some(p) = self[mv.from].as_mut() {
+            p.position = mv.from;
+            if mv.promotion.is_some() {
+                p.variant = PieceVariant::Pawn;
+            }
+        }
+        self[mv.to] = captured;
+    }
+
+    /// all moves the pieces of the given color can make, moves which leave the own king attacked included
+    /// a pawn reaching the last rank has one move for each piece it can be promoted to
+    pub fn moves(&self, color: Color) -> Vec<Move> {
+        let mut moves = vec![];
+        for p in self.board_array.iter().flatten().flatten().filter(|p| p.color == color) {
+            for to in p.get_available_moves(self) {
+                let mv = Move::of_piece(*p, to);
+                if mv.promotion.is_some() {
+                    moves.extend(PROMOTIONS.map(|variant| Move { promotion: Some(variant), ..mv }));
+                } else {
+                    moves.push(mv);
+                }
+            }
+        }
+        moves
+    }
+
+    /// a move of the given color capturing the opponent's king, if there is one
+    pub fn king_capture(&self, color: Color) -> Option<Move> {
+        let king = self.king_position(color.opponent())?;
+        self.moves(color).into_iter().find(|mv| mv.to == king)
+    }
+
+    /// exports the position in Forsyth-Edwards Notation
+    /// castling and en passant are not part of the rules of this game, so they are never available
+    pub fn to_fen(&self, side_to_move: Color, fullmove_number: u32) -> String {
+        let mut placement = String::new();
+        for (row_index, row) in self.board_array.iter().enumerate() {
+            let mut empty = 0;
+            for square in row {
+                match square {
+                    Some(p) => {
+                        if empty > 0 {
+                            placement.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        placement.push(p.fen_char());
+                    }
+                    None => empty += 1,
+                }
+            }
+            if empty > 0 {
+                placement.push_str(&empty.to_string());
+            }
+            if row_index < 7 {
+                placement.push('/');
+            }
+        }
+        let side = match side_to_move {
+            White => 'w',
+            Black => 'b',
+        };
+        format!("{placement} {side} - - 0 {fullmove_number}")
+    }
+
+    /// returns the position of the king of the given color, if it is still on the board
+    pub fn king_position(&self, color: Color) -> Option<Position> {
+        self.board_array
+            .iter()
+            .flatten()
+            .flatten()
+            .find(|p| p.variant == PieceVariant::King && p.color == color)
+            .map(|p| p.position)
+    }
+
+    /// checks if the king of the given color is attacked by any piece of the opponent
+    pub fn is_in_check(&self, color: Color) -> bool {
+        let Some(king_pos) = self.king_position(color) else {
+            return false;
+        };
+        self.board_array
+            .iter()
+            .flatten()
+            .flatten()
+            .filter(|p| p.color != color)
+            .any(|p| p.get_available_moves(self).contains(&king_pos))
     }
 
     fn fill_with_pieces(row: &mut [Option<Piece>; 8], row_index: i8, color: Color) {
@@ -130,32 +320,30 @@ impl fmt::Display for Board {
                 PieceVariant::King => 'K',
             });
 
-            piece_str.push(match p.position {
-                Position { col: _row, row: col } => match col {
-                    0 => 'A',
-                    1 => 'B',
-                    2 => 'C',
-                    3 => 'D',
-                    4 => 'E',
-                    5 => 'F',
-                    6 => 'G',
-                    7 => 'H',
-                    _ => '?',
-                },
+            let Position { col: _row, row: col } = p.position;
+            piece_str.push(match col {
+                0 => 'A',
+                1 => 'B',
+                2 => 'C',
+                3 => 'D',
+                4 => 'E',
+                5 => 'F',
+                6 => 'G',
+                7 => 'H',
+                _ => '?',
             });
 
-            piece_str.push(match p.position {
-                Position { col: row, row: _col } => match row {
-                    0 => '8',
-                    1 => '7',
-                    2 => '6',
-                    3 => '5',
-                    4 => '4',
-                    5 => '3',
-                    6 => '2',
-                    7 => '1',
-                    _ => '?',
-                },
+            let Position { col: row, row: _col } = p.position;
+            piece_str.push(match row {
+                0 => '8',
+                1 => '7',
+                2 => '6',
+                3 => '5',
+                4 => '4',
+                5 => '3',
+                6 => '2',
+                7 => '1',
+                _ => '?',
             });
 
             piece_str.push(' ');
@@ -0,0 +1,94 @@
+use crate::pieces::Color;
+
+use std::time::{Duration, Instant};
+
+/// a chess clock with a base time and an increment per move, e.g. `5+3`
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct TimeControl {
+    pub base: Duration,
+    pub increment: Duration,
+}
+
+impl TimeControl {
+    /// parses `<minutes>+<increment in seconds>`, e.g. `5+3` or `0.5+0`
+    pub fn parse(text: &str) -> Option<TimeControl> {
+        let (minutes, increment) = text.split_once('+').unwrap_or((text, "0"));
+        let minutes: f64 = minutes.trim().parse().ok()?;
+        let increment: f64 = increment.trim().parse().ok()?;
+        if minutes <= 0.0 || increment < 0.0 {
+            return None;
+        }
+        Some(TimeControl {
+            base: Duration::from_secs_f64(minutes * 60.0),
+            increment: Duration::from_secs_f64(increment),
+        })
+    }
+}
+
+/// keeps track of the remaining time of both players, a game without a time control has no clock at all
+#[derive(Clone, Debug)]
+pub struct Clock {
+    pub time_control: TimeControl,
+    white_remaining: Duration,
+    black_remaining: Duration,
+    // the color whose time is running and since when
+    running: Option<(Color, Instant)>,
+}
+
+impl Clock {
+    pub fn new(time_control: TimeControl) -> Self {
+        Clock {
+            time_control,
+            white_remaining: time_control.base,
+            black_remaining: time_control.base,
+            running: None,
+        }
+    }
+
+    /// the remaining time of a player, the time of a running clock is deducted
+    pub fn remaining(&self, color: Color) -> Duration {
+        let stored = match color {
+            Color::White => self.white_remaining,
+            Color::Black => self.black_remaining,
+        };
+        match self.running {
+            Some((running, since)) if running == color => stored.saturating_sub(since.elapsed()),
+            _ => stored,
+        }
+    }
+
+    pub fn start(&mut self, color: Color) {
+        self.running = Some((color, Instant::now()));
+    }
+
+    /// stops the running clock after a move and adds the increment
+    /// returns false if the player ran out of time before completing the move
+    pub fn stop(&mut self) -> bool {
+        let Some((color, _)) = self.running else {
+            return true;
+        };
+        let remaining = self.remaining(color);
+        self.running = None;
+        let stored = match color {
+            Color::White => &mut self.white_remaining,
+            Color::Black => &mut self.black_remaining,
+        };
+        if remaining.is_zero() {
+            *stored = Duration::ZERO;
+            return false;
+        }
+        *stored = remaining + self.time_control.increment;
+        true
+    }
+
+    /// formats the remaining time as `mm:ss`, or `m:ss.t` in the last ten seconds
+    pub fn format(&self, color: Color) -> String {
+        let remaining = self.remaining(color);
+        let seconds = remaining.as_secs();
+        if seconds < 10 {
+            format!("0:{:02}.{}", seconds, remaining.subsec_millis() / 100)
+        } else {
+            format!("{:02}:{:02}", seconds / 60, seconds % 60)
+        }
+    }
+}
@@ -0,0 +1,317 @@
+use crate::analysis::DEFAULT_LINES;
+use crate::clock::TimeControl;
+use crate::engine::{DEFAULT_HASH_MB, MAX_HASH_MB, MAX_MULTI_PV, MAX_THREADS};
+use crate::game::Game;
+use crate::glyphs::GlyphSet;
+use crate::i18n::Language;
+use crate::player::Seat;
+use crate::skill::{Skill, MAX_ELO, MAX_SKILL, MIN_ELO};
+use crate::theme::{parse_hex_color, ColorDepth, Theme};
+use crate::user_input::KeyBindings;
+
+use crossterm::style::Color;
+
+use std::path::PathBuf;
+use std::{env, fs};
+
+const CONFIG_FILE_NAME: &str = "config";
+
+/// settings of the game, read from the config file and overridden by command line arguments
+///
+/// the config file consists of `key = value` lines, lines starting with `#` are ignored:
+/// ```text
+/// theme = brown
+/// color_depth = 256
+/// auto_flip = true
+/// hint_cursor = false
+/// pieces = letters
+/// language = de
+/// key.select = Enter, Space
+/// renderer = text
+/// color.dark_square = #b58863
+/// black = uci
+/// uci_engine = /usr/bin/stockfish
+/// uci.Skill Level = 5
+/// engine_depth = 4
+/// engine_hash = 64
+/// engine_threads = 4
+/// skill = 8
+/// book = /usr/share/games/performance.bin
+/// syzygy_path = /usr/share/syzygy
+/// analysis_lines = 4
+/// time = 5+3
+/// ```
+pub struct Config {
+    pub auto_flip: bool,
+    pub theme: Theme,
+    pub color_depth: ColorDepth,
+    pub glyphs: GlyphSet,
+    pub language: Language,
+    pub key_bindings: KeyBindings,
+    // None picks the text renderer if the output is no terminal
+    pub text_renderer: Option<bool>,
+    pub white: Seat,
+    pub black: Seat,
+    /// search depth of the built-in engine and of external UCI engines in plies
+    pub engine_depth: u8,
+    /// size of the transposition table of the built-in engine in megabytes
+    pub engine_hash: usize,
+    /// threads searching for the built-in engine
+    pub engine_threads: usize,
+    /// strength of the built-in engine, set as a level or a target rating
+    pub engine_skill: Skill,
+    /// Polyglot opening book of the built-in engine
+    pub book: Option<PathBuf>,
+    /// directory of Syzygy endgame tablebases used by the built-in engine
+    pub syzygy_path: Option<PathBuf>,
+    /// whether a hint moves the cursor onto the suggested piece
+    pub hint_cursor: bool,
+    /// number of best lines shown in the analysis
+    pub analysis_lines: usize,
+    /// the position the analysis starts from in Forsyth-Edwards Notation, None for the initial position
+    pub fen: Option<String>,
+    /// the external engine seated with plain `uci`
+    pub uci_engine: Option<String>,
+    /// options sent to external engines with `setoption`
+    pub uci_options: Vec<(String, String)>,
+    // None plays without a clock
+    pub time_control: Option<TimeControl>,
+    // colors of the config file which replace single colors of the selected theme
+    color_overrides: Vec<(String, String)>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            auto_flip: false,
+            theme: Theme::default(),
+            color_depth: ColorDepth::detect(),
+            glyphs: GlyphSet::default(),
+            language: Language::detect(),
+            key_bindings: KeyBindings::default(),
+            text_renderer: None,
+            white: Seat::Human,
+            black: Seat::Human,
+            engine_depth: 4,
+            engine_hash: DEFAULT_HASH_MB,
+            engine_threads: 1,
+            engine_skill: Skill::default(),
+            book: None,
+            syzygy_path: None,
+            hint_cursor: true,
+            analysis_lines: DEFAULT_LINES,
+            fen: None,
+            uci_engine: None,
+            uci_options: vec![],
+            time_control: None,
+            color_overrides: vec![],
+        }
+    }
+}
+
+impl Config {
+    /// location of the config file if none is given with `--config`
+    pub fn default_path() -> Option<PathBuf> {
+        let config_dir = env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("APPDATA").map(PathBuf::from))
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+        Some(config_dir.join("rusty-chess").join(CONFIG_FILE_NAME))
+    }
+
+    /// builds the config from the config file and the given command line arguments
+    pub fn load(args: &[String]) -> Result<Config, String> {
+        let (config, words) = Config::load_with_words(args)?;
+        match words.first() {
+            Some(word) => Err(format!("unknown argument '{word}'")),
+            None => Ok(config),
+        }
+    }
+
+    /// like `load`, but the words which are neither an option nor its value are returned instead of being
+    /// refused, e.g. the fields of a position
+    pub fn load_with_words(args: &[String]) -> Result<(Config, Vec<String>), String> {
+        let mut config = Config::default();
+
+        let explicit_path = args
+            .iter()
+            .position(|arg| arg == "--config")
+            .map(|i| args.get(i + 1).map(PathBuf::from).ok_or("--config expects a path"))
+            .transpose()?;
+        match (&explicit_path, Config::default_path()) {
+            (Some(path), _) => {
+                let content = fs::read_to_string(path)
+                    .map_err(|e| format!("could not read config file {}: {e}", path.display()))?;
+                config.apply_file(&content)?;
+            }
+            (None, Some(path)) => {
+                // a missing config file at the default location is not an error
+                if let Ok(content) = fs::read_to_string(path) {
+                    config.apply_file(&content)?;
+                }
+            }
+            (None, None) => (),
+        }
+
+        let words = config.apply_args(args)?;
+        config.apply_color_overrides()?;
+        Ok((config, words))
+    }
+
+    /// the theme with all overrides applied, reduced to the color depth of the terminal
+    pub fn display_theme(&self) -> Theme {
+        self.theme.for_color_depth(self.color_depth)
+    }
+
+    fn apply_file(&mut self, content: &str) -> Result<(), String> {
+        for (line_number, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(format!("config line {}: expected `key = value`", line_number + 1));
+            };
+            self.set(key.trim(), value.trim())
+                .map_err(|e| format!("config line {}: {e}", line_number + 1))?;
+        }
+        Ok(())
+    }
+
+    /// applies the options and returns the other words in their order
+    fn apply_args(&mut self, args: &[String]) -> Result<Vec<String>, String> {
+        let mut words = vec![];
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or(format!("{arg} expects a value"));
+            match arg.as_str() {
+                "--auto-flip" => self.auto_flip = true,
+                "--config" => {
+                    value()?;
+                }
+                "--theme" => self.set("theme", value()?)?,
+                "--colors" => self.set("color_depth", value()?)?,
+                "--pieces" => self.set("pieces", value()?)?,
+                "--ascii" => self.glyphs = GlyphSet::Letters,
+                "--lang" => self.set("language", value()?)?,
+                "--renderer" => self.set("renderer", value()?)?,
+                "--white" => self.set("white", value()?)?,
+                "--black" => self.set("black", value()?)?,
+                "--depth" => self.set("engine_depth", value()?)?,
+                "--hash" => self.set("engine_hash", value()?)?,
+                "--threads" => self.set("engine_threads", value()?)?,
+                "--skill" => self.set("skill", value()?)?,
+                "--elo" => self.set("elo", value()?)?,
+                "--book" => self.set("book", value()?)?,
+                "--syzygy" => self.set("syzygy_path", value()?)?,
+                "--lines" => self.set("analysis_lines", value()?)?,
+                "--fen" => {
+                    let fen = value()?;
+                    Game::from_fen(fen, None)?;
+                    self.fen = Some(fen.to_string());
+                }
+                "--time" => self.set("time", value()?)?,
+                "--engine" => self.set("uci_engine", value()?)?,
+                "--uci-option" => {
+                    let option = value()?;
+                    let (name, value) = option.split_once('=').ok_or(format!("--uci-option expects name=value, got '{option}'"))?;
+                    self.set(&format!("uci.{}", name.trim()), value.trim())?
+                }
+                _ if arg.starts_with("--") => return Err(format!("unknown argument '{arg}'")),
+                _ => words.push(arg.clone()),
+            }
+        }
+        Ok(words)
+    }
+
+    /// sets a single setting by its key in the config file
+    fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        match key {
+            "auto_flip" => self.auto_flip = parse_bool(value)?,
+            "hint_cursor" => self.hint_cursor = parse_bool(value)?,
+            "theme" => self.theme = Theme::by_name(value).ok_or(format!("unknown theme '{value}'"))?,
+            "color_depth" => {
+                self.color_depth = ColorDepth::from_name(value).ok_or(format!("unknown color depth '{value}'"))?
+            }
+            "pieces" => {
+                self.glyphs = GlyphSet::by_name(value)
+                    .or_else(|| GlyphSet::from_chars(value))
+                    .ok_or(format!("unknown piece set '{value}', expected unicode, letters or twelve characters"))?
+            }
+            "renderer" => match value {
+                "console" => self.text_renderer = Some(false),
+                "text" => self.text_renderer = Some(true),
+                _ => return Err(format!("unknown renderer '{value}', expected console or text")),
+            },
+            "white" | "black" => {
+                let seat = Seat::from_name(value).ok_or(format!(
+                    "unknown player '{value}', expected human, engine, uci:<path>, connect:<host>:<port> or listen:[<address>:]<port>"
+                ))?;
+                if key == "white" { self.white = seat } else { self.black = seat }
+            }
+            "engine_depth" => match value.parse() {
+                Ok(depth) if depth > 0 => self.engine_depth = depth,
+                _ => return Err(format!("invalid engine depth '{value}', expected a number of plies")),
+            },
+            "engine_hash" => match value.parse() {
+                Ok(megabytes) if (1..=MAX_HASH_MB).contains(&megabytes) => self.engine_hash = megabytes,
+                _ => return Err(format!("invalid hash size '{value}', expected 1 to {MAX_HASH_MB} megabytes")),
+            },
+            "engine_threads" => match value.parse() {
+                Ok(threads) if (1..=MAX_THREADS).contains(&threads) => self.engine_threads = threads,
+                _ => return Err(format!("invalid number of threads '{value}', expected 1 to {MAX_THREADS}")),
+            },
+            "skill" => match value.parse() {
+                Ok(level) if (1..=MAX_SKILL).contains(&level) => self.engine_skill = Skill::new(level),
+                _ => return Err(format!("invalid skill level '{value}', expected 1 to {MAX_SKILL}")),
+            },
+            "elo" => match value.parse() {
+                Ok(elo) if (MIN_ELO..=MAX_ELO).contains(&elo) => self.engine_skill = Skill::from_elo(elo),
+                _ => return Err(format!("invalid rating '{value}', expected {MIN_ELO} to {MAX_ELO}")),
+            },
+            "time" => match value {
+                "none" | "off" => self.time_control = None,
+                _ => {
+                    self.time_control =
+                        Some(TimeControl::parse(value).ok_or(format!("invalid time control '{value}', expected e.g. 5+3"))?)
+                }
+            },
+            "book" => self.book = Some(PathBuf::from(value)),
+            "syzygy_path" => self.syzygy_path = Some(PathBuf::from(value)),
+            "analysis_lines" => match value.parse() {
+                Ok(lines) if (1..=MAX_MULTI_PV).contains(&lines) => self.analysis_lines = lines,
+                _ => return Err(format!("invalid number of lines '{value}', expected 1 to {MAX_MULTI_PV}")),
+            },
+            "uci_engine" => self.uci_engine = Some(value.to_string()),
+            "language" => self.language = Language::from_name(value).ok_or(format!("unknown language '{value}'"))?,
+            _ => match (key.strip_prefix("color."), key.strip_prefix("key."), key.strip_prefix("uci.")) {
+                (Some(color_key), _, _) => self.color_overrides.push((color_key.to_string(), value.to_string())),
+                (_, Some(action), _) => self.key_bindings.set(action, value)?,
+                (_, _, Some(option)) => self.uci_options.push((option.to_string(), value.to_string())),
+                _ => return Err(format!("unknown setting '{key}'")),
+            },
+        }
+        Ok(())
+    }
+
+    fn apply_color_overrides(&mut self) -> Result<(), String> {
+        for (key, value) in &self.color_overrides {
+            let color = parse_hex_color(value).ok_or(format!("invalid color '{value}', expected e.g. #769354"))?;
+            self.theme.set_color(key, color)?;
+        }
+        Ok(())
+    }
+
+    /// the colors of the config file replacing those of the theme, checked by `load`
+    pub fn theme_colors(&self) -> Vec<(String, Color)> {
+        self.color_overrides.iter().filter_map(|(key, value)| Some((key.clone(), parse_hex_color(value)?))).collect()
+    }
+}
+
+fn parse_bool(value: &str) -> Result<bool, String> {
+    match value {
+        "true" | "yes" | "on" => Ok(true),
+        "false" | "no" | "off" => Ok(false),
+        _ => Err(format!("expected true or false, got '{value}'")),
+    }
+}
@@ -1,5 +1,11 @@
-use crate::board::Board;
+use crate::analysis::AnalysisView;
+use crate::board::{Board, Move};
+use crate::glyphs::GlyphSet;
+use crate::i18n::{tr, Message};
 use crate::pieces::{Color as PieceColor, Piece, Position};
+use crate::renderer::Renderer;
+use crate::theme::Theme;
+use crate::user_input::{KeyBindings, KeyPress};
 
 use crossterm::{
     cursor, execute, queue,
@@ -9,14 +15,149 @@ use crossterm::{
 use std::ops::Div;
 use std::io::{self, Write};
 
-const BOARD_SIZE_X: u16 = 16;
-const BOARD_SIZE_Y: u16 = 8;
-const X_OFFSET_BORDER: u16 = 7;
-const X_OFFSET_BOARD: u16 = 8;
-const Y_OFFSET_BOARD: u16 = 6;
+const BOARD_SIZE: u16 = 8;
+const X_OFFSET_BORDER: u16 = 6;
+const Y_OFFSET_BORDER: u16 = 5;
+const X_OFFSET_BOARD: u16 = X_OFFSET_BORDER + 2;
+const Y_OFFSET_BOARD: u16 = Y_OFFSET_BORDER + 1;
+const MAX_SCALE: u16 = 4;
+// the heading and one line per action
+const CONTROL_LINES: u16 = 12;
+// the clock is shown above the board, messages below it
+const CLOCK_LINE: u16 = 3;
 
-fn unicode_to_character(piece: &Piece) -> char {
-    std::char::from_u32(piece.get_figure()).unwrap_or('�')
+/// draws the game with colors and cursor movements of crossterm
+pub struct ConsoleRenderer<W: Write> {
+    out: W,
+}
+
+impl<W: Write> ConsoleRenderer<W> {
+    pub fn new(out: W) -> Self {
+        ConsoleRenderer { out }
+    }
+}
+
+impl<W: Write> Renderer for ConsoleRenderer<W> {
+    fn init(&mut self, board: &Board, cursor_pos: &(u16, u16), move_set: &[Position], hint: Option<Move>, settings: &DisplaySettings) -> io::Result<()> {
+        init_display(&mut self.out, board, cursor_pos, move_set, hint, settings)
+    }
+
+    fn board(&mut self, board: &Board, cursor_pos: &(u16, u16), move_set: &[Position], hint: Option<Move>, settings: &DisplaySettings) -> io::Result<()> {
+        display_board(&mut self.out, board, cursor_pos, move_set, hint, settings)
+    }
+
+    fn status(&mut self, clock: Option<&str>, message: Option<&str>, settings: &DisplaySettings) -> io::Result<()> {
+        display_status(&mut self.out, clock, message, settings)
+    }
+
+    fn analysis(&mut self, analysis: Option<&AnalysisView>, settings: &DisplaySettings) -> io::Result<()> {
+        display_analysis(&mut self.out, analysis, settings)
+    }
+
+    fn game_over(&mut self, result: &str) -> io::Result<()> {
+        display_game_over(&mut self.out, result)
+    }
+
+    fn square_at(&self, column: u16, row: u16, flipped: bool) -> Option<Position> {
+        screen_to_board(column, row, flipped)
+    }
+}
+
+/// settings which change how the board is drawn
+#[derive(Clone, Default)]
+pub struct DisplaySettings {
+    pub flipped: bool,
+    pub theme: Theme,
+    pub glyphs: GlyphSet,
+    /// colors of the config file, applied again to every theme chosen at runtime
+    pub color_overrides: Vec<(String, Color)>,
+    // used to list the keys of each action in the controls
+    pub key_bindings: KeyBindings,
+}
+
+/// sizes of everything drawn, computed from the size of the terminal
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Layout {
+    // every square is 2 * scale cells wide and scale cells high
+    scale: u16,
+    show_controls: bool,
+}
+
+impl Layout {
+    /// the layout for the current size of the terminal, None if the terminal is too small for the board
+    pub fn current() -> Option<Layout> {
+        let (columns, rows) = terminal::size().ok()?;
+        Layout::for_size(columns, rows)
+    }
+
+    /// picks the largest squares which fit into the terminal, the controls are only shown if there is room left
+    pub fn for_size(columns: u16, rows: u16) -> Option<Layout> {
+        let fits = |layout: &Layout| layout.frame_width() <= columns && layout.total_height() <= rows;
+        let with_controls = (1..=MAX_SCALE).rev().map(|scale| Layout { scale, show_controls: true }).find(fits);
+        with_controls.or_else(|| Some(Layout { scale: 1, show_controls: false }).filter(fits))
+    }
+
+    /// the smallest terminal size (columns, rows) the board can be drawn in
+    pub fn minimum_size() -> (u16, u16) {
+        let layout = Layout { scale: 1, show_controls: false };
+        (layout.frame_width(), layout.total_height())
+    }
+
+    fn square_width(&self) -> u16 {
+        2 * self.scale
+    }
+
+    fn border_width(&self) -> u16 {
+        BOARD_SIZE * self.square_width() + 4
+    }
+
+    fn border_height(&self) -> u16 {
+        BOARD_SIZE * self.scale + 2
+    }
+
+    fn frame_width(&self) -> u16 {
+        self.border_width() + 12
+    }
+
+    fn frame_height(&self) -> u16 {
+        self.border_height() + 7
+    }
+
+    fn total_height(&self) -> u16 {
+        self.frame_height() + if self.show_controls { CONTROL_LINES } else { 0 }
+    }
+
+    /// the top left terminal cell of a square given in screen coordinates
+    fn square_origin(&self, screen_col: u16, screen_row: u16) -> (u16, u16) {
+        (X_OFFSET_BOARD + screen_col * self.square_width(), Y_OFFSET_BOARD + screen_row * self.scale)
+    }
+
+    /// offset of the piece from the top left cell of its square, the piece is drawn in the middle
+    fn piece_offset(&self) -> (u16, u16) {
+        (self.scale - 1, (self.scale - 1) / 2)
+    }
+}
+
+/// maps a board coordinate (column, row) to the coordinate it is drawn at on the screen
+/// the mapping is its own inverse, so it also maps screen coordinates back onto the board
+fn to_screen_coordinates(col: u16, row: u16, flipped: bool) -> (u16, u16) {
+    if flipped {
+        (BOARD_SIZE - 1 - col, BOARD_SIZE - 1 - row)
+    } else {
+        (col, row)
+    }
+}
+
+/// maps a terminal cell (column, row) back onto the square of the board drawn there
+fn screen_to_board(column: u16, row: u16, flipped: bool) -> Option<Position> {
+    let layout = Layout::current()?;
+    let x = column.checked_sub(X_OFFSET_BOARD)?.div(layout.square_width());
+    let y = row.checked_sub(Y_OFFSET_BOARD)?.div(layout.scale);
+    if x >= BOARD_SIZE || y >= BOARD_SIZE {
+        return None;
+    }
+    let (col, row) = to_screen_coordinates(x, y, flipped);
+    Some(Position::new(col as i8, row as i8))
 }
 
 pub fn position_to_character(int: u16) -> Option<char> {
@@ -33,197 +174,260 @@ pub fn position_to_character(int: u16) -> Option<char> {
     }
 }
 
-pub fn init_display(board: &Board, cursor_pos: &(u16, u16), move_set: &Vec<Position>) -> io::Result<()> {
-    const X_OFFSET_BORDER: u16 = 6;
-    const Y_OFFSET_BORDER: u16 = 5;
+fn init_display(stdout: &mut impl Write, board: &Board, cursor_pos: &(u16, u16), move_set: &[Position], hint: Option<Move>, settings: &DisplaySettings) -> io::Result<()> {
+    let DisplaySettings { flipped, theme, .. } = *settings;
 
-    let mut stdout = io::stdout();
+    execute!(stdout, ResetColor, terminal::Clear(terminal::ClearType::All))?;
 
-    execute!(stdout, terminal::Clear(terminal::ClearType::All))?;
+    let Some(layout) = Layout::current() else {
+        let (columns, rows) = Layout::minimum_size();
+        queue!(
+            stdout,
+            cursor::MoveTo(0, 0),
+            Print(tr(Message::TerminalTooSmall).replace("{}", &format!("{columns}x{rows}")))
+        )?;
+        return stdout.flush();
+    };
 
-    queue!(
-        stdout,
-        cursor::MoveTo(0, BOARD_SIZE_X + 1),
-        PrintStyledContent("Bedienung:".italic()),
-        Print("\n Bewegung: Pfeiltasten ←→↑↓\n Figur auswählen: ENTER\n Figur Bewegen: Pfeiltasten ←→↑↓; Besätigung mit ENTER\n Figur abwählen: BACKSPACE\n Spiel beenden: ESC")
-    )?;
+    if layout.show_controls {
+        queue!(
+            stdout,
+            cursor::MoveTo(0, layout.frame_height()),
+            PrintStyledContent(tr(Message::Controls).italic()),
+        )?;
+        let bindings = &settings.key_bindings;
+        for (line, (message, keys)) in [
+            (Message::ControlMove, bindings.describe_movement()),
+            (Message::ControlSelect, bindings.describe(KeyPress::Enter)),
+            (Message::ControlMovePiece, bindings.describe(KeyPress::Enter)),
+            (Message::ControlDeselect, bindings.describe(KeyPress::BackSpace)),
+            (Message::ControlFlip, bindings.describe(KeyPress::Flip)),
+            (Message::ControlTheme, bindings.describe(KeyPress::Theme)),
+            (Message::ControlGoto, bindings.describe(KeyPress::Goto)),
+            (Message::ControlResign, bindings.describe(KeyPress::Resign)),
+            (Message::ControlDraw, bindings.describe(KeyPress::OfferDraw)),
+            (Message::ControlHint, bindings.describe(KeyPress::Hint)),
+            (Message::ControlQuit, bindings.describe(KeyPress::Esc)),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            queue!(
+                stdout,
+                cursor::MoveTo(1, layout.frame_height() + 1 + line as u16),
+                Print(tr(message).replace("{}", &keys))
+            )?;
+        }
+    }
 
     queue!(
         stdout,
         cursor::MoveTo(3, 2),
-        SetBackgroundColor(Color::Blue),
-        Print("Willkommen zu "),
+        SetForegroundColor(theme.label),
+        SetBackgroundColor(theme.frame),
+        Print(tr(Message::Welcome)),
         PrintStyledContent("rusty-chess".italic()),
-        SetBackgroundColor(Color::Blue),
+        SetForegroundColor(theme.label),
+        SetBackgroundColor(theme.frame),
         Print("!")
     )?;
 
-    for y in 0..17 {
-        for x in 0..32 {
-            if (y == 0 || y == 17 - 1) || (x == 0 || x == 32 - 1) {
+    let (frame_width, frame_height) = (layout.frame_width(), layout.frame_height());
+    for y in 0..frame_height {
+        for x in 0..frame_width {
+            if (y == 0 || y == frame_height - 1) || (x == 0 || x == frame_width - 1) {
                 queue!(stdout, cursor::MoveTo(x, y), Print(" "))?;
             }
         }
     }
 
     // draw border
-    for x in 0..BOARD_SIZE_X + 4 {
-        for y in 0..BOARD_SIZE_Y + 2 {
-            if y == 0 || y == BOARD_SIZE_Y + 1 {
-                queue!(
-                    stdout,
-                    cursor::MoveTo(x + X_OFFSET_BORDER, y + Y_OFFSET_BORDER),
-                    SetForegroundColor(Color::White),
-                    SetBackgroundColor(Color::Grey),
-                    if x % 2 == 0 {
-                        match position_to_character(x.div_ceil(2)) {
-                            Some(c) => PrintStyledContent(c.to_string().italic()),
-                            None => PrintStyledContent(" ".to_string().stylize()),
-                        }
-                    } else {
-                        PrintStyledContent(" ".to_string().stylize())
-                    }
-                )?;
-            } else if x == 1 || x == BOARD_SIZE_X + 2 {
-                if y == 0 || y == 9 {
-                    continue;
-                }
-                queue!(
-                    stdout,
-                    cursor::MoveTo(x + X_OFFSET_BORDER, y + Y_OFFSET_BORDER),
-                    SetForegroundColor(Color::White),
-                    SetBackgroundColor(Color::Grey),
-                    PrintStyledContent((BOARD_SIZE_Y + 1 - y).to_string().italic())
-                )?;
-            } else if x == 0 || x == BOARD_SIZE_X + 3 {
-                if y == 0 || y == 9 {
-                    continue;
-                }
-                queue!(
-                    stdout,
-                    cursor::MoveTo(x + X_OFFSET_BORDER, y + Y_OFFSET_BORDER),
-                    SetForegroundColor(Color::White),
-                    SetBackgroundColor(Color::Grey),
-                    Print(" ")
-                )?;
+    let (border_width, border_height) = (layout.border_width(), layout.border_height());
+    let (piece_offset_x, piece_offset_y) = layout.piece_offset();
+    queue!(stdout, SetForegroundColor(theme.label), SetBackgroundColor(theme.border))?;
+    for y in 0..border_height {
+        for x in 0..border_width {
+            if y == 0 || y == border_height - 1 || x < 2 || x >= border_width - 2 {
+                queue!(stdout, cursor::MoveTo(x + X_OFFSET_BORDER, y + Y_OFFSET_BORDER), Print(" "))?;
             }
         }
     }
+    for i in 0..BOARD_SIZE {
+        let (screen_col, screen_row) = (i, i);
+        let (board_col, board_row) = to_screen_coordinates(i, i, flipped);
+        let (square_x, square_y) = layout.square_origin(screen_col, screen_row);
+        let file = position_to_character(board_col + 1).unwrap_or('?').to_string();
+        let rank = (BOARD_SIZE - board_row).to_string();
+        for label_y in [Y_OFFSET_BORDER, Y_OFFSET_BORDER + border_height - 1] {
+            queue!(
+                stdout,
+                cursor::MoveTo(square_x + piece_offset_x, label_y),
+                PrintStyledContent(file.clone().italic())
+            )?;
+        }
+        for label_x in [X_OFFSET_BORDER + 1, X_OFFSET_BORDER + border_width - 2] {
+            queue!(
+                stdout,
+                cursor::MoveTo(label_x, square_y + piece_offset_y),
+                PrintStyledContent(rank.clone().italic())
+            )?;
+        }
+    }
 
-    display_board(board, cursor_pos, move_set)
+    display_board(stdout, board, cursor_pos, move_set, hint, settings)
 }
 
-pub fn display_board(board: &Board, cursor_pos: &(u16, u16), move_set: &Vec<Position>) -> io::Result<()> {   
-    let mut stdout = io::stdout();
+fn display_board(stdout: &mut impl Write, board: &Board, cursor_pos: &(u16, u16), move_set: &[Position], hint: Option<Move>, settings: &DisplaySettings) -> io::Result<()> {
+    let DisplaySettings { flipped, theme, .. } = *settings;
+    // the message about the too small terminal stays until it is resized
+    let Some(layout) = Layout::current() else {
+        return Ok(());
+    };
+
+    let last_move = board.last_move;
+    let king_in_check: Vec<Position> = [PieceColor::White, PieceColor::Black]
+        .into_iter()
+        .filter(|c| board.is_in_check(*c))
+        .filter_map(|c| board.king_position(c))
+        .collect();
 
     // draw pieces onto board
-    for x in (0..BOARD_SIZE_X).step_by(2) {
-        for y in 0..BOARD_SIZE_Y {
-            let board_coordinate_x = x.div(2);
-            let piece = board.board_array[y as usize][board_coordinate_x as usize];
-            if (board_coordinate_x + y) % 2 == 0 {
-                // white
-                display_field(x, y, piece, &stdout, Color::Rgb { r: 234, g: 234, b: 234 })?;
+    for x in 0..BOARD_SIZE {
+        for y in 0..BOARD_SIZE {
+            let (board_coordinate_x, board_coordinate_y) = to_screen_coordinates(x, y, flipped);
+            let position = Position::new(board_coordinate_x as i8, board_coordinate_y as i8);
+            let piece = board[position];
+            let is_white_field = (board_coordinate_x + board_coordinate_y) % 2 == 0;
+            let background_color = if king_in_check.contains(&position) {
+                theme.check
+            } else if last_move.is_some_and(|(from, to)| from == position || to == position) {
+                if is_white_field { theme.last_move_light } else { theme.last_move_dark }
+            } else if is_white_field {
+                theme.light_square
             } else {
-                // black
-                display_field(x, y, piece, &stdout, Color::Rgb { r: 118, g: 147, b: 84 })?;
-            }
+                theme.dark_square
+            };
+            display_field(x, y, piece, stdout, settings, &layout, background_color)?;
         }
     }
 
+    // the squares of a suggested move
+    for square in hint.iter().flat_map(|mv| [mv.from, mv.to]) {
+        let (screen_x, screen_y) = to_screen_coordinates(square.col as u16, square.row as u16, flipped);
+        display_field(screen_x, screen_y, board[square], stdout, settings, &layout, theme.hint)?;
+    }
+
     // display available moves
-    for i in move_set.into_iter() {
-        let board_coordinate_x: u16 = i.col as u16 * 2;
-        let piece = board[*i];
-        queue!(
-            stdout,
-            cursor::MoveTo(board_coordinate_x + X_OFFSET_BOARD, i.row as u16 + Y_OFFSET_BOARD),
-            SetBackgroundColor(Color::Rgb { r: 249, g: 215, b: 0 }), // rgb(249, 215, 0)
-            match piece {
-                Some(piece) => {
-                    match piece.color {
-                        PieceColor::Black => SetForegroundColor(Color::Black),
-                        PieceColor::White => SetForegroundColor(Color::White),
-                    };
-                    Print(unicode_to_character(&piece).to_string())
-                },
-                None => Print(" ".to_string()),
-            }
-        )?;
-        queue!(
-            stdout,
-            cursor::MoveTo(board_coordinate_x + X_OFFSET_BOARD + 1, i.row as u16 + Y_OFFSET_BOARD),
-            SetBackgroundColor(Color::Rgb { r: 249, g: 215, b: 0 }),
-            Print(" ")
-        )?;
+    for i in move_set.iter() {
+        let (screen_x, screen_y) = to_screen_coordinates(i.col as u16, i.row as u16, flipped);
+        display_field(screen_x, screen_y, board[*i], stdout, settings, &layout, theme.move_highlight)?;
     }
 
-    let selected_piece = board.board_array[cursor_pos.1 as usize][cursor_pos.0 as usize];
     // draw cursor
-    queue!(
-        stdout,
-        cursor::MoveTo(cursor_pos.0 * 2 + X_OFFSET_BOARD , cursor_pos.1 + Y_OFFSET_BOARD),
-        SetBackgroundColor(Color::Red),
-        match selected_piece {
-            Some(piece) => Print(unicode_to_character(&piece).to_string()),
-            None => Print(" ".to_string()),
-        },
-        ResetColor
-    )?;
-    queue!(
-        stdout,
-        cursor::MoveTo(cursor_pos.0 * 2 + X_OFFSET_BOARD + 1, cursor_pos.1 + Y_OFFSET_BOARD),
-        SetForegroundColor(Color::Blue),
-        SetBackgroundColor(Color::Red),
-        Print(" "),
-        ResetColor
-    )?;
+    let cursor_field = Position::new(cursor_pos.0 as i8, cursor_pos.1 as i8);
+    let (cursor_x, cursor_y) = to_screen_coordinates(cursor_pos.0, cursor_pos.1, flipped);
+    display_field(cursor_x, cursor_y, board[cursor_field], stdout, settings, &layout, theme.cursor)?;
 
-    queue!(stdout, cursor::MoveTo(0, 17))?;
+    queue!(stdout, ResetColor, cursor::MoveTo(0, layout.frame_height()))?;
 
     stdout.flush()?;
     Ok(())
 }
 
-fn display_field(x: u16, y: u16, piece: Option<Piece>, mut stdout: &io::Stdout, background_color: Color) -> io::Result<()> {
+/// draws a single square given in screen coordinates
+fn display_field(
+    x: u16,
+    y: u16,
+    piece: Option<Piece>,
+    stdout: &mut impl Write,
+    settings: &DisplaySettings,
+    layout: &Layout,
+    background_color: Color,
+) -> io::Result<()> {
+    let theme = settings.theme;
+    let (square_x, square_y) = layout.square_origin(x, y);
+    let (piece_offset_x, piece_offset_y) = layout.piece_offset();
+    let empty_row = " ".repeat(layout.square_width() as usize);
+
+    queue!(stdout, SetBackgroundColor(background_color))?;
+    for row in 0..layout.scale {
+        queue!(stdout, cursor::MoveTo(square_x, square_y + row), Print(&empty_row))?;
+    }
     queue!(
         stdout,
-        cursor::MoveTo(x + X_OFFSET_BOARD, y + Y_OFFSET_BOARD),
-        SetBackgroundColor(background_color), // Color::White -> Beige 234, 234, 208
+        cursor::MoveTo(square_x + piece_offset_x, square_y + piece_offset_y),
+        SetForegroundColor(theme.piece),
         match piece {
-            Some(piece) => {
-                match piece.color {
-                    PieceColor::Black => SetForegroundColor(Color::Black),
-                    PieceColor::White => SetForegroundColor(Color::White),
-                };
-                Print(unicode_to_character(&piece))
-            },
+            Some(piece) => Print(settings.glyphs.glyph(&piece)),
             None => Print(' '),
         }
-    )?;
-    queue!(
-        stdout,
-        cursor::MoveTo(x + X_OFFSET_BORDER + 2, y + Y_OFFSET_BOARD),
-        SetBackgroundColor(background_color),
-        Print(" ")
     )
 }
 
-pub fn display_winner(color: PieceColor) -> io::Result<()> {
-    let mut stdout = io::stdout();
+/// writes the clock line above and the message line below the board, both inside the frame
+fn display_status(stdout: &mut impl Write, clock: Option<&str>, message: Option<&str>, settings: &DisplaySettings) -> io::Result<()> {
+    let Some(layout) = Layout::current() else {
+        return Ok(());
+    };
+    let width = (layout.frame_width() - 6) as usize;
+    queue!(stdout, SetForegroundColor(settings.theme.label), SetBackgroundColor(settings.theme.frame))?;
+    for (row, text) in [(CLOCK_LINE, clock), (layout.frame_height() - 2, message)] {
+        let text: String = text.unwrap_or_default().chars().take(width).collect();
+        queue!(stdout, cursor::MoveTo(3, row), Print(format!("{text:width$}")))?;
+    }
+    queue!(stdout, ResetColor, cursor::MoveTo(0, layout.frame_height()))?;
+    stdout.flush()
+}
 
-    queue!(
-        stdout,
-        cursor::MoveTo(2, 4),
-    )?;
+/// draws the evaluation bar between the border and the frame and the depth and the best lines in place of the controls
+fn display_analysis(stdout: &mut impl Write, analysis: Option<&AnalysisView>, settings: &DisplaySettings) -> io::Result<()> {
+    let Some(layout) = Layout::current() else {
+        return Ok(());
+    };
+    let theme = settings.theme;
+
+    // white fills the bar from its own side of the board
+    let (bar_x, bar_height) = (X_OFFSET_BORDER + layout.border_width() + 1, layout.border_height());
+    let white_rows = analysis.map(|analysis| (analysis.white_share() * bar_height as f64).round() as u16);
+    for row in 0..bar_height {
+        let from_white = if settings.flipped { row } else { bar_height - 1 - row };
+        let color = match white_rows {
+            Some(white_rows) if from_white < white_rows => theme.eval_white,
+            Some(_) => theme.eval_black,
+            None => Color::Reset,
+        };
+        queue!(stdout, cursor::MoveTo(bar_x, Y_OFFSET_BORDER + row), SetBackgroundColor(color), Print("   "))?;
+    }
 
-    println!("Spieler {:} hat gewonnen!", color);
+    if layout.show_controls {
+        // the lines are padded to the width of the terminal to overwrite the controls
+        let width = terminal::size().map(|(columns, _)| columns as usize).unwrap_or_default().saturating_sub(1);
+        let heading: String = analysis.map(AnalysisView::heading).unwrap_or_default().chars().take(width).collect();
+        queue!(
+            stdout,
+            ResetColor,
+            cursor::MoveTo(0, layout.frame_height()),
+            PrintStyledContent(format!("{heading:width$}").italic())
+        )?;
+        for row in 1..CONTROL_LINES {
+            let line = analysis.and_then(|analysis| analysis.lines.get(row as usize - 1));
+            let text: String = line.map(|line| line.chars().take(width.saturating_sub(1)).collect()).unwrap_or_default();
+            queue!(stdout, cursor::MoveTo(1, layout.frame_height() + row), Print(format!("{text:0$}", width.saturating_sub(1))))?;
+        }
+    }
+    queue!(stdout, ResetColor, cursor::MoveTo(0, layout.frame_height()))?;
+    stdout.flush()
+}
 
+fn display_game_over(stdout: &mut impl Write, result: &str) -> io::Result<()> {
     queue!(
         stdout,
-        cursor::MoveTo(0, BOARD_SIZE_X + 6),
-        Print(" ")
+        ResetColor,
+        cursor::MoveTo(2, 4),
+        Print(result),
+        cursor::MoveTo(2, 5),
+        Print(tr(Message::PressAnyKey)),
     )?;
 
-    Ok(())
-}
\ No newline at end of file
+    stdout.flush()
+}
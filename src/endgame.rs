@@ -0,0 +1,204 @@
+use crate::board::Board;
+use crate::config::Config;
+use crate::engine::Engine;
+use crate::game::{self, Game, GameResult, WinReason};
+use crate::human::HumanPlayer;
+use crate::i18n::{tr, Message};
+use crate::pieces::{Color, PieceVariant, Position};
+use crate::player::{Action, Player};
+use crate::random::Random;
+use crate::retrograde::Tables;
+use crate::ui::Ui;
+use crate::user_input::{self, KeyBindings, KeyPress};
+
+use std::rc::Rc;
+
+// moves beyond the optimum the player gets before the game counts as drawn, like the fifty-move rule
+const EXTRA_MOVES: u32 = 50;
+// generated positions take at least this many plies until the king is captured, so they are no one-move exercise
+const MIN_PLIES: u8 = 7;
+
+/// the classic endgames which can be trained
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Endgame {
+    QueenVsKing,
+    RookVsKing,
+    BishopKnightVsKing,
+    PawnVsKing,
+}
+
+impl Endgame {
+    pub const ALL: [Endgame; 4] = [Endgame::QueenVsKing, Endgame::RookVsKing, Endgame::BishopKnightVsKing, Endgame::PawnVsKing];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Endgame::QueenVsKing => "KQ vs K",
+            Endgame::RookVsKing => "KR vs K",
+            Endgame::BishopKnightVsKing => "KBN vs K",
+            Endgame::PawnVsKing => "KP vs K",
+        }
+    }
+
+    /// the pieces of the attacker beside the king
+    fn pieces(self) -> &'static [PieceVariant] {
+        match self {
+            Endgame::QueenVsKing => &[PieceVariant::Queen],
+            Endgame::RookVsKing => &[PieceVariant::Rook],
+            Endgame::BishopKnightVsKing => &[PieceVariant::Bishop, PieceVariant::Knight],
+            Endgame::PawnVsKing => &[PieceVariant::Pawn],
+        }
+    }
+
+    /// a random position of the endgame with white as the attacker to move
+    fn generate(self, tables: &Tables, random: &mut Random) -> Board {
+        loop {
+            let mut board = Board::new();
+            let kings = [(PieceVariant::King, Color::White), (PieceVariant::King, Color::Black)];
+            for (variant, color) in kings.into_iter().chain(self.pieces().iter().map(|variant| (*variant, Color::White))) {
+                // a pawn stands between the 2nd and the 7th rank
+                let rows = if variant == PieceVariant::Pawn { 1..7 } else { 0..8 };
+                let square = loop {
+                    let square = Position::new(random.below(8) as i8, rows.start + random.below(rows.len() as u64) as i8);
+                    if board[square].is_none() {
+                        break square;
+                    }
+                };
+                board.place(variant, color, square);
+            }
+            let plies = tables.distance(&board, Color::White, Color::White).unwrap_or(0);
+            if plies >= MIN_PLIES && !board.is_in_check(Color::Black) {
+                return board;
+            }
+        }
+    }
+}
+
+/// the board mirrored from the a-file to the h-file and, with swapped colors, from the 1st to the 8th rank
+fn transformed(board: &Board, mirror_files: bool, swap_colors: bool) -> Board {
+    let mut transformed = Board::new();
+    for p in board.board_array.iter().flatten().flatten() {
+        let col = if mirror_files { 7 - p.position.col } else { p.position.col };
+        let (row, color) = if swap_colors { (7 - p.position.row, p.color.opponent()) } else { (p.position.row, p.color) };
+        transformed.place(p.variant, color, Position::new(col, row));
+    }
+    transformed
+}
+
+/// the engine defending against the player, perfectly from the tables as long as they know the position
+struct Defender {
+    engine: Engine,
+    tables: Rc<Tables>,
+}
+
+impl Player for Defender {
+    fn choose(&mut self, game: &Game, _ui: &mut Ui) -> Action {
+        let best = self.tables.best_defence(&game.board, game.side_to_move);
+        match best.or_else(|| self.engine.search(&game.board, game.side_to_move, None).best_move) {
+            Some(mv) => Action::Move(mv),
+            None => Action::Resign,
+        }
+    }
+
+    /// the defender holds on until the end
+    fn accept_draw(&mut self, _game: &Game, _ui: &mut Ui) -> bool {
+        false
+    }
+
+    fn name(&self) -> String {
+        format!("rusty-chess (depth {})", self.engine.depth)
+    }
+}
+
+/// the endgame trainer, started with `rusty-chess endgames`: the player picks an endgame from the menu and plays
+/// random positions of it against the engine, which tells how many moves the conversion should have taken
+pub fn run(config: &Config, ui: &mut Ui) {
+    let bindings = ui.settings.key_bindings.clone();
+    let mut random = Random::from_time();
+    let mut tables = Rc::new(Tables::default());
+    let mut selected = 0;
+    while let Some(endgame) = choose(&mut selected, &bindings, ui) {
+        ui.set_message(Some(tr(Message::EndgameComputing).to_string()));
+        Rc::get_mut(&mut tables).expect("the players of the last round are gone").solve(endgame.pieces());
+        while play(endgame, &tables, config, &bindings, &mut random, ui) {}
+    }
+}
+
+/// the menu of the endgames, None if the player quits
+fn choose(selected: &mut usize, bindings: &KeyBindings, ui: &mut Ui) -> Option<Endgame> {
+    let board = Board::new();
+    let keys = format!("{} {}", bindings.describe(KeyPress::Left), bindings.describe(KeyPress::Right));
+    ui.hint = None;
+    ui.move_set = vec![];
+    ui.init(&board);
+    ui.set_message(Some(tr(Message::EndgameChoose).replace("{}", &keys)));
+    loop {
+        let menu: Vec<String> = Endgame::ALL
+            .iter()
+            .enumerate()
+            .map(|(i, endgame)| if i == *selected { format!("[{}]", endgame.name()) } else { endgame.name().to_string() })
+            .collect();
+        ui.set_header(Some(menu.join(" ")));
+        match user_input::await_user_input(bindings) {
+            Some(KeyPress::Left) => *selected = (*selected + Endgame::ALL.len() - 1) % Endgame::ALL.len(),
+            Some(KeyPress::Right) => *selected = (*selected + 1) % Endgame::ALL.len(),
+            Some(KeyPress::Enter) => return Some(Endgame::ALL[*selected]),
+            Some(KeyPress::Esc) => return None,
+            Some(KeyPress::Resize) => ui.init(&board),
+            _ => (),
+        }
+    }
+}
+
+/// plays one random position of the endgame, returns whether the player wants another one
+fn play(endgame: Endgame, tables: &Rc<Tables>, config: &Config, bindings: &KeyBindings, random: &mut Random, ui: &mut Ui) -> bool {
+    // both sides and both wings are trained, the colors are swapped by mirroring the ranks
+    let swap_colors = random.below(2) == 1;
+    let board = transformed(&endgame.generate(tables, random), random.below(2) == 1, swap_colors);
+    let attacker = if swap_colors { Color::Black } else { Color::White };
+    let mut game = Game::from_fen(&board.to_fen(attacker, 1), None).expect("the generated position is valid");
+    let optimum = tables.distance(&game.board, attacker, attacker).filter(|plies| *plies > 0).map(|plies| (plies as u32).div_ceil(2));
+    game.max_moves = Some(optimum.unwrap_or(0) + EXTRA_MOVES);
+
+    let mut engine = Engine::new(config.engine_depth, config.engine_hash);
+    engine.threads = config.engine_threads;
+    let opponent: Box<dyn Player> = Box::new(Defender { engine, tables: tables.clone() });
+    let mut human = HumanPlayer::new(attacker, bindings.clone());
+    human.hint_cursor = config.hint_cursor;
+    human.hint_hash = config.engine_hash;
+    human.hint_threads = config.engine_threads;
+    let human: Box<dyn Player> = Box::new(human);
+    let mut players = if attacker == Color::White { [human, opponent] } else { [opponent, human] };
+
+    let target = match optimum {
+        Some(moves) => tr(Message::EndgameOptimum).replace("{}", &moves.to_string()),
+        None => tr(Message::EndgameWin).to_string(),
+    };
+    ui.set_header(Some(format!("{}  {target}", endgame.name())));
+    ui.set_message(None);
+    ui.settings.flipped = attacker == Color::Black;
+    let result = game::run(&mut game, &mut players, ui, false);
+
+    let moves = (game.moves.len() as u32).div_ceil(2);
+    let report = match result {
+        GameResult::Aborted => return false,
+        GameResult::Won(winner, WinReason::KingCaptured) if winner == attacker => {
+            let converted = tr(Message::EndgameConverted).replace("{}", &moves.to_string());
+            match optimum {
+                Some(optimum) if moves <= optimum => format!("{converted} {}", tr(Message::EndgameOptimal)),
+                Some(optimum) => format!("{converted} {}", tr(Message::EndgameSlower).replace("{}", &optimum.to_string())),
+                None => converted,
+            }
+        }
+        GameResult::Won(..) | GameResult::Draw => tr(Message::EndgameNotConverted).to_string(),
+    };
+    let keys = tr(Message::EndgameNext).replace("{}", &bindings.describe(KeyPress::Enter));
+    ui.set_message(Some(format!("{report}  {keys}")));
+    loop {
+        match user_input::await_user_input(bindings) {
+            Some(KeyPress::Enter) => return true,
+            Some(KeyPress::Esc) => return false,
+            Some(KeyPress::Resize) => ui.init(&game.board),
+            _ => (),
+        }
+    }
+}
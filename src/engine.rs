@@ -0,0 +1,547 @@
+use crate::board::{Board, Move};
+use crate::book::Book;
+use crate::eval::evaluate;
+use crate::game::Game;
+use crate::pieces::{Color, PieceVariant};
+use crate::player::{Action, Player};
+use crate::random::Random;
+use crate::skill::Skill;
+use crate::syzygy::{Tablebases, Wdl};
+use crate::time_manager::TimeLimits;
+use crate::tt::{Bound, Entry, TranspositionTable};
+use crate::ui::Ui;
+use crate::zobrist;
+
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Instant;
+
+// the engine accepts a draw offer once it is behind by more than this
+const DRAW_ACCEPT_MARGIN: i32 = 200;
+
+/// score of capturing the king, reduced by the number of plies it takes to get there
+pub const MATE_SCORE: i32 = 100_000;
+// scores beyond this announce a captured king
+pub const MATE_THRESHOLD: i32 = MATE_SCORE - 1000;
+const INFINITY: i32 = MATE_SCORE + 1;
+/// score of a position the tablebases call won, reduced by the plies it takes to get there
+pub const TB_WIN_SCORE: i32 = MATE_THRESHOLD - 1000;
+// scores beyond this come from the tablebases
+pub const TB_WIN_THRESHOLD: i32 = TB_WIN_SCORE - 1000;
+/// megabytes of the transposition table if nothing else is configured
+pub const DEFAULT_HASH_MB: usize = 16;
+pub const MAX_HASH_MB: usize = 1024;
+pub const MAX_THREADS: usize = 256;
+/// the deepest search, used when searching on time or until stopped
+pub const MAX_DEPTH: u8 = 64;
+/// the most lines searched at once with MultiPV
+pub const MAX_MULTI_PV: usize = 8;
+// the soft time limit grows by these shares when the best move changes or the score drops by more than the margin
+const BEST_MOVE_CHANGE_EXTENSION: f64 = 0.5;
+const SCORE_DROP_EXTENSION: f64 = 0.5;
+const SCORE_DROP_MARGIN: i32 = 30;
+// the stop flag and the time are checked and the shared node count updated after this many nodes
+const NODE_BATCH: u64 = 1024;
+
+/// the built-in computer opponent, an alpha-beta search following the rules of this game:
+/// there is no check mate, the game ends as soon as a king is captured
+pub struct Engine {
+    /// search depth in plies
+    pub depth: u8,
+    tt: Arc<TranspositionTable>,
+    // set from outside to end the search early, the result of the last complete iteration is used
+    stop: Arc<AtomicBool>,
+    stopped: bool,
+    nodes: u64,
+    // nodes of all threads of the current search, counted in batches
+    node_counter: Arc<AtomicU64>,
+    root_best: Option<Move>,
+    /// number of threads searching the same position, all but one are helpers filling the transposition table
+    pub threads: usize,
+    // the time limits of the current search and when it started
+    limits: Option<TimeLimits>,
+    start: Instant,
+    /// below full strength the engine searches less and chooses worse moves now and then
+    pub skill: Skill,
+    random: Random,
+    /// number of best lines searched, each further line leaves out the first moves of the lines before it
+    pub multi_pv: usize,
+    // root moves left out while a further line is searched
+    excluded: Vec<Move>,
+    /// Syzygy tablebases deciding the root and ending the search in positions with few pieces
+    pub tablebases: Option<Arc<Tablebases>>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct SearchResult {
+    pub best_move: Option<Move>,
+    /// score in centipawns from the perspective of the side to move
+    pub score: i32,
+    /// the depth of the last complete iteration
+    pub depth: u8,
+    pub nodes: u64,
+    /// the expected continuation, starting with the best move
+    pub pv: Vec<Move>,
+    /// the best lines with MultiPV, the first is the one of the best move
+    pub lines: Vec<Line>,
+    /// the plies until the next capture or pawn move if the tablebases decide the position, positive if the side
+    /// to move wins
+    pub tablebase: Option<i32>,
+}
+
+/// a continuation the search expects and its score from the perspective of the side to move
+#[derive(Clone, Debug, Default)]
+pub struct Line {
+    pub score: i32,
+    pub pv: Vec<Move>,
+}
+
+impl Engine {
+    pub fn new(depth: u8, hash_mb: usize) -> Self {
+        Engine::with_table(depth, Arc::new(TranspositionTable::new(hash_mb)), Arc::new(AtomicBool::new(false)))
+    }
+
+    /// an engine sharing the transposition table and the stop flag with its owner
+    pub fn with_table(depth: u8, tt: Arc<TranspositionTable>, stop: Arc<AtomicBool>) -> Self {
+        Engine {
+            depth: depth.max(1),
+            tt,
+            stop,
+            stopped: false,
+            nodes: 0,
+            node_counter: Arc::new(AtomicU64::new(0)),
+            root_best: None,
+            threads: 1,
+            limits: None,
+            start: Instant::now(),
+            skill: Skill::default(),
+            random: Random::from_time(),
+            multi_pv: 1,
+            excluded: vec![],
+            tablebases: None,
+        }
+    }
+
+    /// forgets everything learned in earlier games
+    pub fn clear(&self) {
+        self.tt.clear();
+    }
+
+    pub fn search(&mut self, board: &Board, color: Color, limits: Option<TimeLimits>) -> SearchResult {
+        self.search_with_info(board, color, limits, &mut |_| ())
+    }
+
+    /// searches with iterative deepening, the result of every complete iteration is passed to `info`
+    ///
+    /// with time limits the search goes as deep as the time allows, otherwise up to the depth of the engine
+    ///
+    /// with more than one thread, helpers search the same position at the same time (Lazy SMP), the results
+    /// they store in the shared transposition table let the main search cut off earlier
+    pub fn search_with_info(
+        &mut self,
+        board: &Board,
+        color: Color,
+        limits: Option<TimeLimits>,
+        info: &mut dyn FnMut(&SearchResult),
+    ) -> SearchResult {
+        // the tables play perfectly, which a weakened engine shouldn't
+        if self.skill.is_full() {
+            if let Some(result) = self.tablebase_result(board, color) {
+                info(&result);
+                return result;
+            }
+        }
+        self.start = Instant::now();
+        self.limits = limits;
+        let max_depth = if limits.is_some() { MAX_DEPTH } else { self.depth };
+        let max_depth = max_depth.min(self.skill.max_depth().unwrap_or(MAX_DEPTH));
+        // a weakened engine doesn't need the help
+        let threads = if self.skill.is_full() { self.threads } else { 1 };
+        self.tt.new_search();
+        self.node_counter = Arc::new(AtomicU64::new(0));
+        let helpers_stop = Arc::new(AtomicBool::new(false));
+        let result = thread::scope(|scope| {
+            for helper in 1..threads {
+                let mut engine = Engine::with_table(max_depth, self.tt.clone(), helpers_stop.clone());
+                engine.node_counter = self.node_counter.clone();
+                engine.tablebases = self.tablebases.clone();
+                // every second helper starts one ply deeper, so the threads spread over more depths
+                let first_depth = 1 + (helper % 2) as u8;
+                scope.spawn(move || engine.iterate(board, color, first_depth, max_depth, &mut |_| ()));
+            }
+            let result = self.iterate(board, color, 1, max_depth, info);
+            helpers_stop.store(true, Ordering::Relaxed);
+            result
+        });
+        if self.skill.is_full() || result.best_move.is_none() {
+            return result;
+        }
+        self.weakened(board, color, result)
+    }
+
+    /// the best moves of a position the tablebases decide, the winning move closest to the next capture or pawn move
+    /// or the losing one furthest from it
+    ///
+    /// a position in which the best move draws is searched, as stalemate loses in this game, unlike in the tables
+    fn tablebase_result(&self, board: &Board, color: Color) -> Option<SearchResult> {
+        let tablebases = self.tablebases.as_ref()?;
+        let ranked = tablebases.rank(board, color)?;
+        let &(best_move, dtz) = ranked.first().filter(|(_, dtz)| *dtz != 0)?;
+        let pv = tablebase_line(tablebases, board, color, best_move);
+        let mut lines = vec![Line { score: tablebase_score(dtz), pv: pv.clone() }];
+        lines.extend(ranked.iter().skip(1).take(self.multi_pv - 1).map(|&(mv, dtz)| Line { score: tablebase_score(dtz), pv: vec![mv] }));
+        Some(SearchResult {
+            best_move: Some(best_move),
+            score: tablebase_score(dtz),
+            depth: pv.len() as u8,
+            nodes: 0,
+            pv,
+            lines,
+            tablebase: Some(dtz),
+        })
+    }
+
+    /// replaces the best move by one chosen with the noise of the skill level among all moves scored
+    /// at the depth the search has reached
+    fn weakened(&mut self, board: &Board, color: Color, result: SearchResult) -> SearchResult {
+        let mut board = board.clone();
+        let hash = zobrist::hash(&board, color);
+        // the hard time limit of the search still applies, on a clock this pass must not lose on time
+        let mut scores = vec![];
+        for mv in board.moves(color) {
+            let piece = board[mv.from].expect("moves start on a piece");
+            let captured = board.make_move(mv);
+            let score = if captured.is_some_and(|p| p.variant == PieceVariant::King) {
+                MATE_SCORE
+            } else {
+                let child_hash = hash ^ zobrist::move_delta(piece, mv, captured);
+                -self.negamax(&mut board, color.opponent(), child_hash, result.depth - 1, 1, -INFINITY, INFINITY)
+            };
+            board.unmake_move(mv, captured);
+            scores.push((mv, score));
+        }
+        // a pass stopped by the time or the stop flag has no reliable scores, the best move is played
+        if self.stopped {
+            return result;
+        }
+        match self.skill.choose(&scores, &mut self.random) {
+            Some(mv) => {
+                let score = scores.iter().find(|(other, _)| *other == mv).map(|(_, score)| *score).unwrap_or(result.score);
+                SearchResult {
+                    best_move: Some(mv),
+                    score,
+                    pv: vec![mv],
+                    lines: vec![Line { score, pv: vec![mv] }],
+                    ..result
+                }
+            }
+            None => result,
+        }
+    }
+
+    fn iterate(
+        &mut self,
+        board: &Board,
+        color: Color,
+        first_depth: u8,
+        max_depth: u8,
+        info: &mut dyn FnMut(&SearchResult),
+    ) -> SearchResult {
+        let mut board = board.clone();
+        let hash = zobrist::hash(&board, color);
+        self.stopped = false;
+        self.nodes = 0;
+        self.root_best = None;
+
+        let mut result = SearchResult::default();
+        for depth in first_depth.min(max_depth)..=max_depth {
+            let score = self.negamax(&mut board, color, hash, depth, 0, -INFINITY, INFINITY);
+            // an interrupted iteration is only better than nothing
+            if self.stopped && result.best_move.is_some() {
+                break;
+            }
+            let best_move = self.root_best;
+            let pv = self.principal_variation(&board, color, depth);
+            let lines = self.further_lines(&mut board, color, hash, depth, Line { score, pv: pv.clone() });
+            let previous = std::mem::replace(
+                &mut result,
+                SearchResult {
+                    best_move,
+                    score,
+                    depth,
+                    nodes: self.node_counter.load(Ordering::Relaxed) + self.nodes % NODE_BATCH,
+                    pv,
+                    lines,
+                    tablebase: None,
+                },
+            );
+            info(&result);
+            let node_limit_reached = self.skill.node_limit().is_some_and(|limit| self.nodes >= limit);
+            if self.stopped || node_limit_reached || !self.has_time_for_next_iteration(&previous, &result) {
+                break;
+            }
+        }
+        result
+    }
+
+    /// searches the root again for the next best lines after the first one, leaving out the moves already found
+    /// a line interrupted by the stop flag is dropped
+    fn further_lines(&mut self, board: &mut Board, color: Color, hash: u64, depth: u8, first: Line) -> Vec<Line> {
+        let mut lines = vec![first];
+        while lines.len() < self.multi_pv && !self.stopped {
+            self.excluded = lines.iter().filter_map(|line| line.pv.first().copied()).collect();
+            self.root_best = None;
+            let score = self.negamax(board, color, hash, depth, 0, -INFINITY, INFINITY);
+            match self.root_best {
+                Some(mv) if !self.stopped && !self.excluded.contains(&mv) => {
+                    lines.push(Line { score, pv: self.principal_variation(board, color, depth) })
+                }
+                _ => break,
+            }
+        }
+        self.excluded.clear();
+        lines
+    }
+
+    /// an iteration takes longer than all before it together, so the next one is only started while there is
+    /// a fair chance to finish it within the soft limit, which is extended while the search is unsure
+    fn has_time_for_next_iteration(&self, previous: &SearchResult, result: &SearchResult) -> bool {
+        let Some(limits) = self.limits else {
+            return true;
+        };
+        // a fixed time per move is used up completely
+        if limits.soft >= limits.hard {
+            return true;
+        }
+        let mut extension = 1.0;
+        if previous.best_move.is_some() && previous.best_move != result.best_move {
+            extension += BEST_MOVE_CHANGE_EXTENSION;
+        }
+        if previous.best_move.is_some() && result.score < previous.score - SCORE_DROP_MARGIN {
+            extension += SCORE_DROP_EXTENSION;
+        }
+        let soft = limits.soft.mul_f64(extension).min(limits.hard);
+        self.start.elapsed() < soft / 2
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn negamax(&mut self, board: &mut Board, color: Color, hash: u64, depth: u8, ply: i32, mut alpha: i32, beta: i32) -> i32 {
+        self.nodes += 1;
+        if self.nodes.is_multiple_of(NODE_BATCH) {
+            self.node_counter.fetch_add(NODE_BATCH, Ordering::Relaxed);
+            let out_of_time = self.limits.is_some_and(|limits| self.start.elapsed() >= limits.hard);
+            if out_of_time || self.stop.load(Ordering::Relaxed) {
+                self.stopped = true;
+            }
+        }
+        if self.stopped {
+            return 0;
+        }
+
+        // capturing the king ends the game, so it is always the best move
+        if let Some(mv) = board.king_capture(color) {
+            if ply == 0 {
+                self.root_best = Some(mv);
+            }
+            return MATE_SCORE - ply;
+        }
+
+        let entry = self.tt.probe(hash, ply);
+        if let Some(entry) = entry.filter(|entry| ply > 0 && entry.depth >= depth) {
+            match entry.bound {
+                Bound::Exact => return entry.score,
+                Bound::Lower if entry.score >= beta => return entry.score,
+                Bound::Upper if entry.score <= alpha => return entry.score,
+                _ => (),
+            }
+        }
+        // the tables know the result, except for their draws, which stalemate may turn into a win here
+        if let Some(tablebases) = self.tablebases.as_ref().filter(|_| ply > 0) {
+            let score = match tablebases.probe_wdl(board, color) {
+                Some(Wdl::Win | Wdl::CursedWin) => Some(TB_WIN_SCORE - ply),
+                Some(Wdl::Loss | Wdl::BlessedLoss) => Some(-TB_WIN_SCORE + ply),
+                _ => None,
+            };
+            if let Some(score) = score {
+                self.tt.store(hash, ply, Entry { depth: MAX_DEPTH, bound: Bound::Exact, score, best_move: None });
+                return score;
+            }
+        }
+        if depth == 0 {
+            return evaluate(board, color);
+        }
+
+        let moves = ordered_moves(board, color, entry.and_then(|entry| entry.best_move));
+        if moves.is_empty() {
+            return 0;
+        }
+        let original_alpha = alpha;
+        let mut best_score = -INFINITY;
+        let mut best_move = None;
+        for mv in moves {
+            if ply == 0 && self.excluded.contains(&mv) {
+                continue;
+            }
+            let piece = board[mv.from].expect("moves start on a piece");
+            let captured = board.make_move(mv);
+            let child_hash = hash ^ zobrist::move_delta(piece, mv, captured);
+            let score = -self.negamax(board, color.opponent(), child_hash, depth - 1, ply + 1, -beta, -alpha);
+            board.unmake_move(mv, captured);
+            if self.stopped {
+                return 0;
+            }
+            if score > best_score {
+                best_score = score;
+                best_move = Some(mv);
+                if ply == 0 {
+                    self.root_best = Some(mv);
+                }
+            }
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        let bound = if best_score <= original_alpha {
+            Bound::Upper
+        } else if best_score >= beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+        // without some of its moves the root has no score of its own
+        if ply > 0 || self.excluded.is_empty() {
+            self.tt.store(hash, ply, Entry { depth, bound, score: best_score, best_move });
+        }
+        best_score
+    }
+
+    /// follows the best moves stored in the transposition table
+    fn principal_variation(&self, board: &Board, color: Color, depth: u8) -> Vec<Move> {
+        let mut board = board.clone();
+        let mut color = color;
+        let mut pv = vec![];
+        let mut seen = vec![];
+        let mut next = self.root_best;
+        while let Some(mv) = next {
+            if pv.len() >= depth as usize || !board.moves(color).contains(&mv) {
+                break;
+            }
+            let captured = board.make_move(mv);
+            pv.push(mv);
+            color = color.opponent();
+            let hash = zobrist::hash(&board, color);
+            if captured.is_some_and(|p| p.variant == PieceVariant::King) || seen.contains(&hash) {
+                break;
+            }
+            seen.push(hash);
+            // positions with a capturable king are never stored
+            next = self.tt.probe(hash, 0).and_then(|entry| entry.best_move).or_else(|| board.king_capture(color));
+        }
+        pv
+    }
+}
+
+/// the score of a position the tablebases decide, by the plies until the next capture or pawn move
+fn tablebase_score(dtz: i32) -> i32 {
+    let plies = dtz.abs().min(TB_WIN_SCORE - TB_WIN_THRESHOLD - 1);
+    match dtz.signum() {
+        1 => TB_WIN_SCORE - plies,
+        -1 => -TB_WIN_SCORE + plies,
+        _ => 0,
+    }
+}
+
+/// the moves the tables choose for both sides, starting with the given one, up to the next capture or pawn move
+fn tablebase_line(tablebases: &Tablebases, board: &Board, color: Color, first: Move) -> Vec<Move> {
+    let mut board = board.clone();
+    let mut color = color;
+    let mut pv = vec![];
+    let mut next = Some(first);
+    while let Some(mv) = next {
+        let zeroing = board[mv.to].is_some() || board[mv.from].is_some_and(|p| p.variant == PieceVariant::Pawn);
+        board.make_move(mv);
+        pv.push(mv);
+        color = color.opponent();
+        if zeroing || pv.len() >= MAX_DEPTH as usize {
+            break;
+        }
+        // after a mate there is no move left
+        next = tablebases.rank(&board, color).and_then(|ranked| ranked.first().filter(|(_, dtz)| *dtz != 0).map(|(mv, _)| *mv));
+    }
+    pv
+}
+
+/// the built-in engine seated at one side of the board, it plays whichever color is to move
+pub struct EnginePlayer {
+    engine: Engine,
+    // the engine plays from the book as long as it knows the position
+    book: Option<Book>,
+}
+
+impl EnginePlayer {
+    pub fn new(engine: Engine, book: Option<Book>) -> Self {
+        EnginePlayer { engine, book }
+    }
+}
+
+impl Player for EnginePlayer {
+    fn choose(&mut self, game: &Game, _ui: &mut Ui) -> Action {
+        if let Some(mv) = self.book.as_mut().and_then(|book| book.pick(game)) {
+            return Action::Move(mv);
+        }
+        // with a clock the engine thinks as long as its remaining time allows, without one up to its depth
+        let limits = game.clock.as_ref().map(|clock| {
+            TimeLimits::new(clock.remaining(game.side_to_move), clock.time_control.increment, None)
+        });
+        match self.engine.search(&game.board, game.side_to_move, limits).best_move {
+            Some(mv) => Action::Move(mv),
+            None => Action::Resign,
+        }
+    }
+
+    fn new_game(&mut self) {
+        self.engine.clear();
+    }
+
+    /// draws are offered on the turn of the opponent
+    fn accept_draw(&mut self, game: &Game, _ui: &mut Ui) -> bool {
+        evaluate(&game.board, game.side_to_move.opponent()) < -DRAW_ACCEPT_MARGIN
+    }
+
+    fn name(&self) -> String {
+        if self.engine.skill.is_full() {
+            format!("rusty-chess (depth {})", self.engine.depth)
+        } else {
+            format!("rusty-chess (skill {})", self.engine.skill.level())
+        }
+    }
+}
+
+/// all moves of a color, the best move of an earlier search first, then captures of valuable pieces
+/// so the search can cut off early
+fn ordered_moves(board: &Board, color: Color, hash_move: Option<Move>) -> Vec<Move> {
+    let mut moves = board.moves(color);
+    moves.sort_by_key(|mv| {
+        if Some(*mv) == hash_move {
+            i32::MIN
+        } else {
+            -board[mv.to].map(|p| piece_value(p.variant)).unwrap_or(0)
+        }
+    });
+    moves
+}
+
+/// the value of a piece for ordering captures and judging exchanges
+pub fn piece_value(variant: PieceVariant) -> i32 {
+    match variant {
+        PieceVariant::Pawn => 100,
+        PieceVariant::Knight => 320,
+        PieceVariant::Bishop => 330,
+        PieceVariant::Rook => 500,
+        PieceVariant::Queen => 900,
+        PieceVariant::King => 20_000,
+    }
+}
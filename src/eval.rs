@@ -0,0 +1,471 @@
+use crate::board::Board;
+use crate::config::Config;
+use crate::game::Game;
+use crate::i18n::{self, tr, Message};
+use crate::pieces::{Color, Piece, PieceVariant, Position};
+
+use std::ops::{AddAssign, Mul};
+
+/// the phase of a board with all pieces, it falls towards 0 as knights, bishops, rooks and queens leave the board
+const FULL_PHASE: i32 = 24;
+
+/// a score for the middlegame and one for the endgame, blended by the phase of the game
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub struct Score {
+    pub mg: i32,
+    pub eg: i32,
+}
+
+impl Score {
+    pub const fn new(mg: i32, eg: i32) -> Self {
+        Score { mg, eg }
+    }
+
+    /// the score for a board of the given phase
+    pub fn taper(self, phase: i32) -> i32 {
+        (self.mg * phase + self.eg * (FULL_PHASE - phase)) / FULL_PHASE
+    }
+}
+
+impl AddAssign for Score {
+    fn add_assign(&mut self, other: Score) {
+        self.mg += other.mg;
+        self.eg += other.eg;
+    }
+}
+
+impl Mul<i32> for Score {
+    type Output = Score;
+
+    fn mul(self, factor: i32) -> Score {
+        Score::new(self.mg * factor, self.eg * factor)
+    }
+}
+
+/// the parts the evaluation is made of
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Term {
+    Material,
+    PieceSquares,
+    PawnStructure,
+    KingSafety,
+    Mobility,
+    BishopPair,
+    RooksOnOpenFiles,
+}
+
+pub const TERMS: [Term; 7] = [
+    Term::Material,
+    Term::PieceSquares,
+    Term::PawnStructure,
+    Term::KingSafety,
+    Term::Mobility,
+    Term::BishopPair,
+    Term::RooksOnOpenFiles,
+];
+
+impl Term {
+    pub fn name(self) -> &'static str {
+        match self {
+            Term::Material => tr(Message::EvalMaterial),
+            Term::PieceSquares => tr(Message::EvalPieceSquares),
+            Term::PawnStructure => tr(Message::EvalPawnStructure),
+            Term::KingSafety => tr(Message::EvalKingSafety),
+            Term::Mobility => tr(Message::EvalMobility),
+            Term::BishopPair => tr(Message::EvalBishopPair),
+            Term::RooksOnOpenFiles => tr(Message::EvalRooksOnOpenFiles),
+        }
+    }
+}
+
+const DOUBLED_PAWN: Score = Score::new(-5, -12);
+const ISOLATED_PAWN: Score = Score::new(-15, -10);
+// bonus of a passed pawn by the number of ranks it has advanced
+const PASSED_PAWN: [Score; 7] = [
+    Score::new(0, 0),
+    Score::new(5, 10),
+    Score::new(10, 20),
+    Score::new(15, 35),
+    Score::new(25, 55),
+    Score::new(40, 80),
+    Score::new(60, 120),
+];
+const PAWN_SHIELD: [Score; 3] = [Score::new(-15, 0), Score::new(10, 0), Score::new(5, 0)];
+const OPEN_FILE_AT_KING: Score = Score::new(-10, 0);
+// for every move of an enemy piece onto a square next to the king
+const KING_ZONE_ATTACK: Score = Score::new(-8, -2);
+const BISHOP_PAIR: Score = Score::new(30, 50);
+const ROOK_ON_OPEN_FILE: Score = Score::new(25, 10);
+const ROOK_ON_HALF_OPEN_FILE: Score = Score::new(12, 6);
+
+/// the evaluation of a board split into its terms for both colors
+pub struct Breakdown {
+    // indexed by term and color, white first
+    scores: [[Score; 2]; TERMS.len()],
+    /// from FULL_PHASE with all pieces on the board down to 0 with only kings and pawns
+    pub phase: i32,
+}
+
+impl Breakdown {
+    pub fn of(board: &Board) -> Self {
+        let mut breakdown = Breakdown {
+            scores: [[Score::default(); 2]; TERMS.len()],
+            phase: 0,
+        };
+        let pieces: Vec<Piece> = board.board_array.iter().flatten().flatten().copied().collect();
+        let mut pawn_files = [[0; 8]; 2];
+        for pawn in pieces.iter().filter(|p| p.variant == PieceVariant::Pawn) {
+            pawn_files[side(pawn.color)][pawn.position.col as usize] += 1;
+        }
+        // the squares every knight, bishop, rook and queen can move to, shared by mobility and king safety
+        let attacks: Vec<(Piece, Vec<Position>)> = pieces
+            .iter()
+            .filter(|p| !matches!(p.variant, PieceVariant::Pawn | PieceVariant::King))
+            .map(|p| (*p, p.get_available_moves(board)))
+            .collect();
+
+        let mut bishops = [0; 2];
+        for piece in &pieces {
+            let color = piece.color;
+            breakdown.add(Term::Material, color, material(piece.variant));
+            breakdown.add(Term::PieceSquares, color, piece_square(piece));
+            breakdown.phase += phase_weight(piece.variant);
+            match piece.variant {
+                PieceVariant::Pawn => breakdown.add(Term::PawnStructure, color, pawn_structure(piece, &pieces, &pawn_files)),
+                PieceVariant::King => breakdown.add(Term::KingSafety, color, king_safety(piece, &pieces, &pawn_files, &attacks)),
+                PieceVariant::Bishop => bishops[side(color)] += 1,
+                PieceVariant::Rook => {
+                    let file = piece.position.col as usize;
+                    if pawn_files[side(color)][file] == 0 {
+                        let bonus = if pawn_files[side(color.opponent())][file] == 0 {
+                            ROOK_ON_OPEN_FILE
+                        } else {
+                            ROOK_ON_HALF_OPEN_FILE
+                        };
+                        breakdown.add(Term::RooksOnOpenFiles, color, bonus);
+                    }
+                }
+                _ => (),
+            }
+        }
+        for (piece, moves) in &attacks {
+            breakdown.add(Term::Mobility, piece.color, mobility(piece.variant, moves.len() as i32));
+        }
+        for color in [Color::White, Color::Black] {
+            if bishops[side(color)] >= 2 {
+                breakdown.add(Term::BishopPair, color, BISHOP_PAIR);
+            }
+        }
+        breakdown.phase = breakdown.phase.min(FULL_PHASE);
+        breakdown
+    }
+
+    fn add(&mut self, term: Term, color: Color, score: Score) {
+        self.scores[term as usize][side(color)] += score;
+    }
+
+    pub fn term(&self, term: Term, color: Color) -> Score {
+        self.scores[term as usize][side(color)]
+    }
+
+    /// the sum of all terms of a color
+    pub fn total(&self, color: Color) -> Score {
+        let mut total = Score::default();
+        for term in TERMS {
+            total += self.term(term, color);
+        }
+        total
+    }
+
+    /// the tapered score of the given color minus that of its opponent
+    pub fn score(&self, color: Color) -> i32 {
+        self.total(color).taper(self.phase) - self.total(color.opponent()).taper(self.phase)
+    }
+
+    /// a table of all terms for both colors, as printed by `rusty-chess eval`
+    pub fn table(&self) -> String {
+        let mut table = format!(
+            "{:24} {:>13}   {:>13}   {:>13}\n{:24} {:>6} {:>6}   {:>6} {:>6}   {:>6} {:>6}\n",
+            tr(Message::EvalTerm),
+            Color::White.to_string(),
+            Color::Black.to_string(),
+            tr(Message::EvalTotal),
+            "",
+            "MG",
+            "EG",
+            "MG",
+            "EG",
+            "MG",
+            "EG"
+        );
+        let mut row = |name: &str, white: Score, black: Score| {
+            table.push_str(&format!(
+                "{name:24} {:>6} {:>6}   {:>6} {:>6}   {:>6} {:>6}\n",
+                white.mg,
+                white.eg,
+                black.mg,
+                black.eg,
+                white.mg - black.mg,
+                white.eg - black.eg
+            ));
+        };
+        for term in TERMS {
+            row(term.name(), self.term(term, Color::White), self.term(term, Color::Black));
+        }
+        row(tr(Message::EvalTotal), self.total(Color::White), self.total(Color::Black));
+        table
+    }
+}
+
+/// the evaluation of the board in centipawns from the perspective of the given color
+pub fn evaluate(board: &Board, color: Color) -> i32 {
+    Breakdown::of(board).score(color)
+}
+
+/// prints the evaluation of a position term by term:
+/// ```text
+/// rusty-chess eval rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b - - 0 1
+/// ```
+pub fn run(args: &[String]) -> Result<(), String> {
+    // all arguments are the position, only the language is taken from the config file
+    i18n::set_language(Config::load(&[])?.language);
+    let game = if args.is_empty() {
+        Game::new(None)
+    } else {
+        Game::from_fen(&args.join(" "), None)?
+    };
+    let breakdown = Breakdown::of(&game.board);
+    print!("{}", breakdown.table());
+    println!();
+    let phase = format!("{}/{FULL_PHASE} ({}%)", breakdown.phase, breakdown.phase * 100 / FULL_PHASE);
+    println!("{}", tr(Message::EvalPhase).replace("{}", &phase));
+    println!("{}", tr(Message::EvalScore).replace("{}", &format!("{:+}", breakdown.score(Color::White))));
+    Ok(())
+}
+
+fn side(color: Color) -> usize {
+    match color {
+        Color::White => 0,
+        Color::Black => 1,
+    }
+}
+
+fn material(variant: PieceVariant) -> Score {
+    match variant {
+        PieceVariant::Pawn => Score::new(100, 120),
+        PieceVariant::Knight => Score::new(320, 300),
+        PieceVariant::Bishop => Score::new(330, 320),
+        PieceVariant::Rook => Score::new(500, 530),
+        PieceVariant::Queen => Score::new(900, 950),
+        // both kings are always on the board while the game lasts
+        PieceVariant::King => Score::new(0, 0),
+    }
+}
+
+fn phase_weight(variant: PieceVariant) -> i32 {
+    match variant {
+        PieceVariant::Knight | PieceVariant::Bishop => 1,
+        PieceVariant::Rook => 2,
+        PieceVariant::Queen => 4,
+        PieceVariant::Pawn | PieceVariant::King => 0,
+    }
+}
+
+/// squares a piece can move to, compared with the number it has on average
+fn mobility(variant: PieceVariant, moves: i32) -> Score {
+    match variant {
+        PieceVariant::Knight => Score::new(4, 4) * (moves - 4),
+        PieceVariant::Bishop => Score::new(5, 5) * (moves - 6),
+        PieceVariant::Rook => Score::new(2, 4) * (moves - 7),
+        PieceVariant::Queen => Score::new(1, 2) * (moves - 13),
+        PieceVariant::Pawn | PieceVariant::King => Score::default(),
+    }
+}
+
+/// how many ranks a pawn has advanced from its starting rank
+fn advancement(pawn: &Piece) -> usize {
+    match pawn.color {
+        Color::White => (6 - pawn.position.row).clamp(0, 6) as usize,
+        Color::Black => (pawn.position.row - 1).clamp(0, 6) as usize,
+    }
+}
+
+/// whether a square is in front of a pawn of the given color
+fn is_ahead(color: Color, pawn: Position, square: Position) -> bool {
+    match color {
+        Color::White => square.row < pawn.row,
+        Color::Black => square.row > pawn.row,
+    }
+}
+
+fn pawn_structure(pawn: &Piece, pieces: &[Piece], pawn_files: &[[i32; 8]; 2]) -> Score {
+    let file = pawn.position.col;
+    let own_files = &pawn_files[side(pawn.color)];
+    let mut score = Score::default();
+    if own_files[file as usize] > 1 {
+        score += DOUBLED_PAWN;
+    }
+    let neighbours = [file - 1, file + 1].into_iter().filter(|f| (0..8).contains(f));
+    if neighbours.map(|f| own_files[f as usize]).sum::<i32>() == 0 {
+        score += ISOLATED_PAWN;
+    }
+    let blocked = pieces.iter().any(|p| {
+        p.variant == PieceVariant::Pawn
+            && p.color != pawn.color
+            && (p.position.col - file).abs() <= 1
+            && is_ahead(pawn.color, pawn.position, p.position)
+    });
+    if !blocked {
+        score += PASSED_PAWN[advancement(pawn)];
+    }
+    score
+}
+
+/// pawns in front of the king, open files next to it and enemy pieces able to move next to it
+fn king_safety(king: &Piece, pieces: &[Piece], pawn_files: &[[i32; 8]; 2], attacks: &[(Piece, Vec<Position>)]) -> Score {
+    let mut score = Score::default();
+    let position = king.position;
+    // the shield only counts for a king on its own side of the board
+    let at_home = match king.color {
+        Color::White => position.row >= 6,
+        Color::Black => position.row <= 1,
+    };
+    for file in (position.col - 1..=position.col + 1).filter(|f| (0..8).contains(f)) {
+        if at_home {
+            let distance = pieces
+                .iter()
+                .filter(|p| p.variant == PieceVariant::Pawn && p.color == king.color && p.position.col == file)
+                .filter(|p| is_ahead(king.color, position, p.position))
+                .map(|p| (p.position.row - position.row).unsigned_abs() as usize)
+                .min();
+            score += PAWN_SHIELD[distance.filter(|d| *d <= 2).unwrap_or(0)];
+        }
+        if pawn_files[side(king.color)][file as usize] == 0 {
+            score += OPEN_FILE_AT_KING;
+        }
+    }
+    let zone_attacks = attacks
+        .iter()
+        .filter(|(piece, _)| piece.color != king.color)
+        .flat_map(|(_, moves)| moves)
+        .filter(|square| (square.row - position.row).abs() <= 1 && (square.col - position.col).abs() <= 1)
+        .count();
+    score += KING_ZONE_ATTACK * zone_attacks as i32;
+    score
+}
+
+fn piece_square(piece: &Piece) -> Score {
+    // the tables are drawn from White's view with the eighth rank on top, like the rows of the board
+    let row = match piece.color {
+        Color::White => piece.position.row,
+        Color::Black => 7 - piece.position.row,
+    };
+    let index = (row * 8 + piece.position.col) as usize;
+    let (mg, eg) = match piece.variant {
+        PieceVariant::Pawn => (&PAWN_MG, &PAWN_EG),
+        PieceVariant::Knight => (&KNIGHT, &KNIGHT),
+        PieceVariant::Bishop => (&BISHOP, &BISHOP),
+        PieceVariant::Rook => (&ROOK, &ROOK),
+        PieceVariant::Queen => (&QUEEN, &QUEEN),
+        PieceVariant::King => (&KING_MG, &KING_EG),
+    };
+    Score::new(mg[index], eg[index])
+}
+
+#[rustfmt::skip]
+const PAWN_MG: [i32; 64] = [
+     0,   0,   0,   0,   0,   0,   0,   0,
+    50,  50,  50,  50,  50,  50,  50,  50,
+    10,  10,  20,  30,  30,  20,  10,  10,
+     5,   5,  10,  25,  25,  10,   5,   5,
+     0,   0,   0,  20,  20,   0,   0,   0,
+     5,  -5, -10,   0,   0, -10,  -5,   5,
+     5,  10,  10, -20, -20,  10,  10,   5,
+     0,   0,   0,   0,   0,   0,   0,   0,
+];
+
+// in the endgame every step towards promotion counts, wherever the pawn is
+#[rustfmt::skip]
+const PAWN_EG: [i32; 64] = [
+     0,   0,   0,   0,   0,   0,   0,   0,
+    60,  60,  60,  60,  60,  60,  60,  60,
+    40,  40,  40,  40,  40,  40,  40,  40,
+    25,  25,  25,  25,  25,  25,  25,  25,
+    15,  15,  15,  15,  15,  15,  15,  15,
+     5,   5,   5,   5,   5,   5,   5,   5,
+     0,   0,   0,   0,   0,   0,   0,   0,
+     0,   0,   0,   0,   0,   0,   0,   0,
+];
+
+#[rustfmt::skip]
+const KNIGHT: [i32; 64] = [
+   -50, -40, -30, -30, -30, -30, -40, -50,
+   -40, -20,   0,   0,   0,   0, -20, -40,
+   -30,   0,  10,  15,  15,  10,   0, -30,
+   -30,   5,  15,  20,  20,  15,   5, -30,
+   -30,   0,  15,  20,  20,  15,   0, -30,
+   -30,   5,  10,  15,  15,  10,   5, -30,
+   -40, -20,   0,   5,   5,   0, -20, -40,
+   -50, -40, -30, -30, -30, -30, -40, -50,
+];
+
+#[rustfmt::skip]
+const BISHOP: [i32; 64] = [
+   -20, -10, -10, -10, -10, -10, -10, -20,
+   -10,   0,   0,   0,   0,   0,   0, -10,
+   -10,   0,   5,  10,  10,   5,   0, -10,
+   -10,   5,   5,  10,  10,   5,   5, -10,
+   -10,   0,  10,  10,  10,  10,   0, -10,
+   -10,  10,  10,  10,  10,  10,  10, -10,
+   -10,   5,   0,   0,   0,   0,   5, -10,
+   -20, -10, -10, -10, -10, -10, -10, -20,
+];
+
+#[rustfmt::skip]
+const ROOK: [i32; 64] = [
+     0,   0,   0,   0,   0,   0,   0,   0,
+     5,  10,  10,  10,  10,  10,  10,   5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+     0,   0,   0,   5,   5,   0,   0,   0,
+];
+
+#[rustfmt::skip]
+const QUEEN: [i32; 64] = [
+   -20, -10, -10,  -5,  -5, -10, -10, -20,
+   -10,   0,   0,   0,   0,   0,   0, -10,
+   -10,   0,   5,   5,   5,   5,   0, -10,
+    -5,   0,   5,   5,   5,   5,   0,  -5,
+     0,   0,   5,   5,   5,   5,   0,  -5,
+   -10,   5,   5,   5,   5,   5,   0, -10,
+   -10,   0,   5,   0,   0,   0,   0, -10,
+   -20, -10, -10,  -5,  -5, -10, -10, -20,
+];
+
+#[rustfmt::skip]
+const KING_MG: [i32; 64] = [
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -20, -30, -30, -40, -40, -30, -30, -20,
+   -10, -20, -20, -20, -20, -20, -20, -10,
+    20,  20,   0,   0,   0,   0,  20,  20,
+    20,  30,  10,   0,   0,  10,  30,  20,
+];
+
+// the king belongs into the center once the pieces are gone
+#[rustfmt::skip]
+const KING_EG: [i32; 64] = [
+   -50, -40, -30, -20, -20, -30, -40, -50,
+   -30, -20, -10,   0,   0, -10, -20, -30,
+   -30, -10,  20,  30,  30,  20, -10, -30,
+   -30, -10,  30,  40,  40,  30, -10, -30,
+   -30, -10,  30,  40,  40,  30, -10, -30,
+   -30, -10,  20,  30,  30,  20, -10, -30,
+   -30, -30,   0,   0,   0,   0, -30, -30,
+   -50, -30, -30, -30, -30, -30, -30, -50,
+];
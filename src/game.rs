@@ -0,0 +1,207 @@
+use crate::board::{Board, KingCaptured, Move};
+use crate::clock::Clock;
+use crate::i18n::{tr, Message};
+use crate::pieces::Color;
+use crate::player::{Action, GameEvent, Player};
+use crate::ui::Ui;
+use crate::zobrist;
+
+/// the state of a game the players decide on
+pub struct Game {
+    pub board: Board,
+    pub side_to_move: Color,
+    pub clock: Option<Clock>,
+    pub fullmove_number: u32,
+    /// the position the game started from and all moves played since
+    pub start_fen: String,
+    pub moves: Vec<Move>,
+    /// the game is drawn once this many full moves have been played since its start position
+    pub max_moves: Option<u32>,
+    // every position reached so far with the side to move, used to find repetitions
+    positions: Vec<u64>,
+}
+
+/// why a game has been won
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum WinReason {
+    KingCaptured,
+    Resignation,
+    Time,
+    IllegalMove,
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum GameResult {
+    Won(Color, WinReason),
+    Draw,
+    /// the game has been quit before it ended
+    Aborted,
+}
+
+impl GameResult {
+    /// the result in the notation of PGN
+    pub fn pgn(self) -> &'static str {
+        match self {
+            GameResult::Won(Color::White, _) => "1-0",
+            GameResult::Won(Color::Black, _) => "0-1",
+            GameResult::Draw => "1/2-1/2",
+            GameResult::Aborted => "*",
+        }
+    }
+
+    /// the text shown at the end of the game, None if it has been quit
+    pub fn describe(self) -> Option<String> {
+        let (message, winner) = match self {
+            GameResult::Won(color, WinReason::KingCaptured) => (Message::PlayerWon, color),
+            GameResult::Won(color, WinReason::Resignation) => (Message::WonByResignation, color),
+            GameResult::Won(color, WinReason::Time) => (Message::WonOnTime, color),
+            GameResult::Won(color, WinReason::IllegalMove) => (Message::WonByIllegalMove, color),
+            GameResult::Draw => return Some(tr(Message::DrawAgreed).to_string()),
+            GameResult::Aborted => return None,
+        };
+        Some(tr(message).replace("{}", &winner.to_string()))
+    }
+}
+
+impl Game {
+    /// a game from the initial position
+    pub fn new(clock: Option<Clock>) -> Self {
+        let mut board = Board::new();
+        board.initialize();
+        let start_fen = board.to_fen(Color::White, 1);
+        Game {
+            positions: vec![zobrist::hash(&board, Color::White)],
+            board,
+            side_to_move: Color::White,
+            clock,
+            fullmove_number: 1,
+            start_fen,
+            moves: vec![],
+            max_moves: None,
+        }
+    }
+
+    /// a game from a position in Forsyth-Edwards Notation, castling rights and en passant squares are ignored
+    pub fn from_fen(fen: &str, clock: Option<Clock>) -> Result<Self, String> {
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        let board = Board::from_fen(fields.first().ok_or("empty position")?)?;
+        let side_to_move = match fields.get(1).copied().unwrap_or("w") {
+            "w" => Color::White,
+            "b" => Color::Black,
+            side => return Err(format!("unknown side to move '{side}' in '{fen}'")),
+        };
+        let fullmove_number = fields.get(5).and_then(|n| n.parse().ok()).unwrap_or(1);
+        Ok(Game {
+            start_fen: board.to_fen(side_to_move, fullmove_number),
+            positions: vec![zobrist::hash(&board, side_to_move)],
+            board,
+            side_to_move,
+            clock,
+            fullmove_number,
+            moves: vec![],
+            max_moves: None,
+        })
+    }
+
+    pub fn fen(&self) -> String {
+        self.board.to_fen(self.side_to_move, self.fullmove_number)
+    }
+
+    /// plays a move of the side to move and hands the turn to the opponent
+    pub fn play(&mut self, mv: Move) -> Result<(), KingCaptured> {
+        let result = self.board.play(mv);
+        self.moves.push(mv);
+        if self.side_to_move == Color::Black {
+            self.fullmove_number += 1;
+        }
+        self.side_to_move = self.side_to_move.opponent();
+        self.positions.push(self.hash());
+        result
+    }
+
+    /// whether the current position has occurred for the third time
+    pub fn is_threefold_repetition(&self) -> bool {
+        let hash = self.hash();
+        self.positions.iter().filter(|position| **position == hash).count() >= 3
+    }
+
+    /// the Zobrist hash of the current position
+    pub fn hash(&self) -> u64 {
+        zobrist::hash(&self.board, self.side_to_move)
+    }
+}
+
+/// lets the players take turns until the game ends
+/// players are given as [white, black], with auto flip the board is turned towards the local player to move
+pub fn run(game: &mut Game, players: &mut [Box<dyn Player>; 2], ui: &mut Ui, auto_flip: bool) -> GameResult {
+    ui.init(&game.board);
+    loop {
+        let color = game.side_to_move;
+        let (index, opponent_index) = match color {
+            Color::White => (0, 1),
+            Color::Black => (1, 0),
+        };
+        if auto_flip && players[index].is_local() {
+            ui.orient_towards(color, &game.board);
+        }
+        // without a clock the line above the board is left to the caller, e.g. for the endgame being trained
+        if let Some(clock) = game.clock.as_mut() {
+            clock.start(color);
+            ui.update_clock(clock);
+        }
+
+        let action = loop {
+            if !players[index].is_local() {
+                ui.set_message(Some(tr(Message::Thinking).replace("{}", &color.to_string())));
+            }
+            let action = players[index].choose(game, ui);
+            if action != Action::OfferDraw {
+                break action;
+            }
+            let accepted = players[opponent_index].accept_draw(game, ui);
+            players[index].notify(GameEvent::DrawAnswered(accepted));
+            if accepted {
+                return GameResult::Draw;
+            }
+            ui.set_message(Some(tr(Message::DrawDeclined).to_string()));
+        };
+        ui.set_message(None);
+
+        match action {
+            Action::Move(mv) => {
+                if game.clock.as_mut().is_some_and(|clock| !clock.stop()) {
+                    players[opponent_index].notify(GameEvent::OutOfTime);
+                    return GameResult::Won(color.opponent(), WinReason::Time);
+                }
+                if !game.board.moves(color).contains(&mv) {
+                    return GameResult::Won(color.opponent(), WinReason::IllegalMove);
+                }
+                let result = game.play(mv);
+                players[opponent_index].notify(GameEvent::Moved(mv));
+                ui.redraw(&game.board);
+                if result.is_err() {
+                    return GameResult::Won(color, WinReason::KingCaptured);
+                }
+                // a game from a later position gets as many moves as one from the initial position
+                let limit_reached = game.max_moves.is_some_and(|max_moves| game.moves.len() as u32 >= 2 * max_moves);
+                if game.is_threefold_repetition() || limit_reached {
+                    return GameResult::Draw;
+                }
+            }
+            Action::Resign => {
+                players[opponent_index].notify(GameEvent::Resigned);
+                return GameResult::Won(color.opponent(), WinReason::Resignation);
+            }
+            Action::OutOfTime => {
+                players[opponent_index].notify(GameEvent::OutOfTime);
+                return GameResult::Won(color.opponent(), WinReason::Time);
+            }
+            Action::Quit => {
+                players[opponent_index].notify(GameEvent::Quit);
+                return GameResult::Aborted;
+            }
+            // draw offers are answered above
+            Action::OfferDraw => unreachable!(),
+        }
+    }
+}
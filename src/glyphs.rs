@@ -0,0 +1,57 @@
+use crate::pieces::{Color, Piece, PieceVariant};
+
+/// the characters used to draw the pieces onto the board
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub enum GlyphSet {
+    /// the unicode chess symbols U+2654 to U+265F
+    #[default]
+    Unicode,
+    /// `KQRBNP` for white and `kqrbnp` for black, readable on every terminal
+    Letters,
+    /// twelve user defined characters in the order `KQRBNPkqrbnp`
+    Custom([char; 12]),
+}
+
+impl GlyphSet {
+    pub fn by_name(name: &str) -> Option<GlyphSet> {
+        match name.to_lowercase().as_str() {
+            "unicode" => Some(GlyphSet::Unicode),
+            "letters" | "ascii" => Some(GlyphSet::Letters),
+            _ => None,
+        }
+    }
+
+    /// builds a custom glyph set from a string of exactly twelve characters
+    pub fn from_chars(chars: &str) -> Option<GlyphSet> {
+        let chars: Vec<char> = chars.chars().collect();
+        Some(GlyphSet::Custom(chars.try_into().ok()?))
+    }
+
+    pub fn glyph(&self, piece: &Piece) -> char {
+        match self {
+            GlyphSet::Unicode => std::char::from_u32(piece.get_figure()).unwrap_or('�'),
+            GlyphSet::Letters => {
+                let letter = "KQRBNP".chars().nth(variant_index(piece.variant)).unwrap_or('?');
+                match piece.color {
+                    Color::White => letter,
+                    Color::Black => letter.to_ascii_lowercase(),
+                }
+            }
+            GlyphSet::Custom(chars) => match piece.color {
+                Color::White => chars[variant_index(piece.variant)],
+                Color::Black => chars[variant_index(piece.variant) + 6],
+            },
+        }
+    }
+}
+
+fn variant_index(variant: PieceVariant) -> usize {
+    match variant {
+        PieceVariant::King => 0,
+        PieceVariant::Queen => 1,
+        PieceVariant::Rook => 2,
+        PieceVariant::Bishop => 3,
+        PieceVariant::Knight => 4,
+        PieceVariant::Pawn => 5,
+    }
+}
@@ -0,0 +1,254 @@
+use crate::board::{Board, Move};
+use crate::engine::{Engine, DEFAULT_HASH_MB, MAX_DEPTH};
+use crate::game::Game;
+use crate::i18n::{tr, Message};
+use crate::notation;
+use crate::pieces::{Color, Piece};
+use crate::player::{Action, Player};
+use crate::time_manager::TimeLimits;
+use crate::ui::Ui;
+use crate::user_input::{self, KeyBindings, KeyPress};
+
+use std::time::Duration;
+
+// how often the running clock is redrawn while waiting for a key
+const CLOCK_TICK: Duration = Duration::from_millis(100);
+// how long the engine looks for a hint, the clock of the player keeps running meanwhile
+const HINT_TIME: Duration = Duration::from_millis(500);
+
+/// a player in front of this terminal, moving the pieces with the keyboard or the mouse
+pub struct HumanPlayer {
+    color: Color,
+    key_bindings: KeyBindings,
+    /// whether a hint moves the cursor onto the suggested piece
+    pub hint_cursor: bool,
+    /// size of the transposition table of the hint engine in megabytes and the threads it searches with
+    pub hint_hash: usize,
+    pub hint_threads: usize,
+    // started with the first hint
+    hint_engine: Option<Engine>,
+}
+
+impl HumanPlayer {
+    pub fn new(color: Color, key_bindings: KeyBindings) -> Self {
+        HumanPlayer {
+            color,
+            key_bindings,
+            hint_cursor: true,
+            hint_hash: DEFAULT_HASH_MB,
+            hint_threads: 1,
+            hint_engine: None,
+        }
+    }
+
+    /// asks the engine for the best move within a short time and highlights its squares until the next move
+    fn show_hint(&mut self, game: &Game, input: &mut MoveInput, ui: &mut Ui) {
+        let engine = self.hint_engine.get_or_insert_with(|| {
+            let mut engine = Engine::new(MAX_DEPTH, self.hint_hash);
+            engine.threads = self.hint_threads;
+            engine
+        });
+        let Some(mv) = engine.search(&game.board, self.color, Some(TimeLimits::fixed(HINT_TIME))).best_move else {
+            return;
+        };
+        ui.hint = Some(mv);
+        ui.set_message(Some(tr(Message::Hint).replace("{}", &notation::to_san(&game.board, mv))));
+        if self.hint_cursor {
+            input.reset(ui);
+            ui.cursor_pos.jump_to(mv.from);
+        }
+        ui.redraw(&game.board);
+    }
+}
+
+impl Player for HumanPlayer {
+    fn choose(&mut self, game: &Game, ui: &mut Ui) -> Action {
+        let board = &game.board;
+        let mut input = MoveInput::default();
+        ui.move_set = vec![];
+        ui.hint = None;
+        ui.redraw(board);
+
+        loop {
+            if let Some(clock) = &game.clock {
+                ui.update_clock(clock);
+                if clock.remaining(self.color).is_zero() {
+                    return Action::OutOfTime;
+                }
+            }
+
+            // expect user input
+            let Some(pressed_key) = user_input::poll_user_input(&self.key_bindings, CLOCK_TICK) else {
+                continue;
+            };
+            match input.handle(pressed_key, board, self.color, ui) {
+                Input::Move(mv) => {
+                    ui.hint = None;
+                    return Action::Move(mv);
+                }
+                Input::Key(KeyPress::Hint) => self.show_hint(game, &mut input, ui),
+                Input::Key(KeyPress::Esc) => return Action::Quit,
+                Input::Key(KeyPress::Resign) => {
+                    ui.set_message(Some(tr(Message::ConfirmResign).to_string()));
+                    let confirmed = user_input::await_confirmation();
+                    ui.set_message(None);
+                    if confirmed {
+                        ui.move_set = vec![];
+                        return Action::Resign;
+                    }
+                }
+                Input::Key(KeyPress::OfferDraw) => {
+                    ui.move_set = vec![];
+                    return Action::OfferDraw;
+                }
+                Input::Key(_) | Input::Handled => (),
+            }
+        }
+    }
+
+    fn accept_draw(&mut self, game: &Game, ui: &mut Ui) -> bool {
+        let offered_by = self.color.opponent().to_string();
+        ui.set_message(Some(tr(Message::DrawOffered).replace("{}", &offered_by)));
+        let accepted = user_input::await_confirmation();
+        ui.set_message(None);
+        ui.redraw(&game.board);
+        accepted
+    }
+
+    fn is_local(&self) -> bool {
+        true
+    }
+
+    fn name(&self) -> String {
+        self.color.to_string()
+    }
+}
+
+/// what a key press means while the player at this terminal picks a move
+pub enum Input {
+    /// a piece has been moved onto one of the squares it can reach
+    Move(Move),
+    /// a key which has nothing to do with moving the pieces, e.g. resign, left to the caller
+    Key(KeyPress),
+    /// the key has been handled, e.g. the cursor has been moved
+    Handled,
+}
+
+/// picks a piece and its target square with the keyboard or the mouse, shared by all modes in which
+/// the player at this terminal moves the pieces
+#[derive(Default)]
+pub struct MoveInput {
+    selected_piece: Option<Piece>,
+}
+
+impl MoveInput {
+    /// drops the selected piece, e.g. because the position has changed
+    pub fn reset(&mut self, ui: &mut Ui) {
+        self.selected_piece = None;
+        ui.move_set = vec![];
+    }
+
+    /// handles a key of the player moving the pieces of the given color and redraws the board
+    pub fn handle(&mut self, pressed_key: KeyPress, board: &Board, color: Color, ui: &mut Ui) -> Input {
+        let flipped = ui.settings.flipped;
+        let pressed_key = match pressed_key {
+            // a click is handled like moving the cursor onto the square and pressing enter
+            KeyPress::MouseDown(column, row) => {
+                let Some(square) = ui.renderer.square_at(column, row, flipped) else {
+                    return Input::Handled;
+                };
+                // clicking a square the selected piece can't reach starts a new selection
+                if self.selected_piece.is_some_and(|p| p.position != square && !ui.move_set.contains(&square)) {
+                    self.reset(ui);
+                }
+                ui.cursor_pos.jump_to(square);
+                KeyPress::Enter
+            }
+            // the cursor follows a dragged piece across the squares it can reach
+            KeyPress::MouseDrag(column, row) => {
+                match (self.selected_piece, ui.renderer.square_at(column, row, flipped)) {
+                    (Some(p), Some(square)) if p.position == square || ui.move_set.contains(&square) => {
+                        ui.cursor_pos.jump_to(square);
+                        ui.redraw(board);
+                    }
+                    _ => (),
+                }
+                return Input::Handled;
+            }
+            // dropping a dragged piece onto a reachable square moves it there
+            KeyPress::MouseUp(column, row) => match (self.selected_piece, ui.renderer.square_at(column, row, flipped)) {
+                (Some(p), Some(square)) if p.position != square && ui.move_set.contains(&square) => {
+                    ui.cursor_pos.jump_to(square);
+                    KeyPress::Enter
+                }
+                _ => return Input::Handled,
+            },
+            key => key.oriented(flipped),
+        };
+
+        match (self.selected_piece, pressed_key) {
+            (None, KeyPress::Left) => ui.cursor_pos.move_cursor_left(),
+            (None, KeyPress::Right) => ui.cursor_pos.move_cursor_right(),
+            (None, KeyPress::Up) => ui.cursor_pos.move_cursor_up(),
+            (None, KeyPress::Down) => ui.cursor_pos.move_cursor_down(),
+            (None, KeyPress::Enter) => {
+                if let Some(p) = board[ui.cursor_pos.into()] {
+                    // prevent white player form selecting black pieces and vice versa
+                    if p.color == color {
+                        self.selected_piece = Some(p);
+                        ui.move_set = p.get_available_moves(board);
+                    }
+                }
+            }
+            (Some(_), KeyPress::Left) => ui.cursor_pos.move_with_piece_left(&ui.move_set),
+            (Some(_), KeyPress::Right) => ui.cursor_pos.move_with_piece_right(&ui.move_set),
+            (Some(_), KeyPress::Up) => ui.cursor_pos.move_with_piece_up(&ui.move_set),
+            (Some(_), KeyPress::Down) => ui.cursor_pos.move_with_piece_down(&ui.move_set),
+            (Some(p), KeyPress::Enter) => {
+                // prevent placing selected_piece on its own square
+                if p.position == ui.cursor_pos.into() {
+                    return Input::Handled;
+                }
+                self.reset(ui);
+                return Input::Move(Move::of_piece(p, ui.cursor_pos.into()));
+            }
+            (_, KeyPress::BackSpace) => self.reset(ui),
+            (None, KeyPress::Goto) => match user_input::await_square_name() {
+                Ok(Some(square)) => ui.cursor_pos.jump_to(square),
+                Ok(None) => (),
+                Err(key) => return Input::Key(key),
+            },
+            (Some(_), KeyPress::Goto) => {
+                // with a selected piece only the squares it can reach may be entered
+                match user_input::await_square_name() {
+                    Ok(Some(square)) if ui.move_set.contains(&square) => ui.cursor_pos.jump_to(square),
+                    Ok(_) => (),
+                    Err(key) => return Input::Key(key),
+                }
+            }
+            (_, KeyPress::Theme) => {
+                let theme = ui.settings.theme.next().with_overrides(&ui.settings.color_overrides);
+                ui.settings.theme = theme.for_color_depth(ui.color_depth);
+                ui.init(board);
+                return Input::Handled;
+            }
+            (_, KeyPress::Resize) => {
+                ui.init(board);
+                return Input::Handled;
+            }
+            (_, KeyPress::Flip) => {
+                ui.settings.flipped = !ui.settings.flipped;
+                // the coordinate labels are part of the border, which is only drawn by init
+                ui.init(board);
+                return Input::Handled;
+            }
+            // mouse events have already been translated above
+            (_, KeyPress::MouseDown(..) | KeyPress::MouseDrag(..) | KeyPress::MouseUp(..)) => return Input::Handled,
+            (_, key) => return Input::Key(key),
+        }
+
+        // redraw board
+        ui.redraw(board);
+        Input::Handled
+    }
+}
@@ -0,0 +1,388 @@
+use std::env;
+use std::sync::OnceLock;
+
+static LANGUAGE: OnceLock<Language> = OnceLock::new();
+
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub enum Language {
+    #[default]
+    English,
+    German,
+}
+
+/// every text shown to the players
+/// the controls contain `{}` as placeholder for the keys bound to the action
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Message {
+    Controls,
+    ControlMove,
+    ControlSelect,
+    ControlMovePiece,
+    ControlDeselect,
+    ControlFlip,
+    ControlTheme,
+    ControlGoto,
+    ControlResign,
+    ControlDraw,
+    ControlHint,
+    ControlQuit,
+    Welcome,
+    /// contains `{}` as placeholder for the color of the winner
+    PlayerWon,
+    PressAnyKey,
+    White,
+    Black,
+    /// contains `{}` as placeholder for the minimum size
+    TerminalTooSmall,
+    /// contains `{}` as placeholder for the move, e.g. `e2-e4`
+    LastMove,
+    /// contains `{}` as placeholder for the color of the king in check
+    Check,
+    /// contains `{}` as placeholder for the color of the player to move
+    Thinking,
+    ConfirmResign,
+    /// contains `{}` as placeholder for the color offering the draw
+    DrawOffered,
+    DrawDeclined,
+    DrawAgreed,
+    /// contains `{}` as placeholder for the color of the winner
+    WonByResignation,
+    /// contains `{}` as placeholder for the color of the winner
+    WonOnTime,
+    /// contains `{}` as placeholder for the color of the winner
+    WonByIllegalMove,
+    /// contains `{}` as placeholder for the keys stepping back and forth
+    AnalysisKeys,
+    /// contains `{}` as placeholder for the key changing the piece on a square
+    AnalysisEditKey,
+    /// contains `{}` as placeholder for the key handing the move to the other side
+    AnalysisSideKey,
+    /// contains `{}` as placeholder for the depth of the search
+    AnalysisDepth,
+    /// contains `{}` as placeholder for the moves until a king is captured or the next capture or pawn move
+    AnalysisTablebaseWin,
+    /// contains `{}` as placeholder for the moves until a king is captured or the next capture or pawn move
+    AnalysisTablebaseLoss,
+    /// contains `{}` as placeholder for the suggested move
+    Hint,
+    ConfirmAnnotate,
+    /// contains `{}` as placeholder for the progress, e.g. `12/80`
+    Annotating,
+    /// contains `{}` as placeholder for the name of the file
+    AnnotationSaved,
+    /// contains `{}` as placeholder for the error
+    AnnotationFailed,
+    /// contains `{}` as placeholder for the id and the rating of the puzzle
+    PuzzleInfo,
+    /// contains `{}` as placeholder for the puzzle rating of the player
+    PuzzleRating,
+    /// contains `{}` as placeholder for the number of puzzles solved in a row
+    PuzzleStreak,
+    /// contains `{}` as placeholder for the longest streak so far
+    PuzzleBestStreak,
+    /// contains `{}` as placeholder for the color of the player
+    PuzzleToMove,
+    PuzzleCorrect,
+    PuzzleSolved,
+    /// contains `{}` as placeholder for the move of the solution
+    PuzzleWrong,
+    /// contains `{}` as placeholder for the keys starting the next puzzle
+    PuzzleNext,
+    PuzzlesDone,
+    /// contains `{}` as placeholder for the keys choosing an endgame
+    EndgameChoose,
+    EndgameComputing,
+    /// contains `{}` as placeholder for the least number of moves needed
+    EndgameOptimum,
+    EndgameWin,
+    /// contains `{}` as placeholder for the number of moves played
+    EndgameConverted,
+    EndgameOptimal,
+    /// contains `{}` as placeholder for the least number of moves needed
+    EndgameSlower,
+    EndgameNotConverted,
+    /// contains `{}` as placeholder for the keys starting the next position
+    EndgameNext,
+    /// contains `{}` as placeholder for the number of the game, e.g. `3/10`
+    MatchGame,
+    MatchEngine,
+    MatchScore,
+    MatchWins,
+    MatchDraws,
+    MatchLosses,
+    /// contains `{}` as placeholder for the difference and its error margin
+    MatchElo,
+    EvalTerm,
+    EvalMaterial,
+    EvalPieceSquares,
+    EvalPawnStructure,
+    EvalKingSafety,
+    EvalMobility,
+    EvalBishopPair,
+    EvalRooksOnOpenFiles,
+    EvalTotal,
+    /// contains `{}` as placeholder for the phase, e.g. `12/24 (50%)`
+    EvalPhase,
+    /// contains `{}` as placeholder for the score in centipawns
+    EvalScore,
+    /// contains `{}` as placeholder for the key in hex
+    BookKey,
+    BookNotFound,
+    BookMove,
+    BookWeight,
+    BookShare,
+    /// contains `{}` as placeholder for the number of the game
+    SummaryGame,
+    /// contains `{}` as placeholder for the first move which could not be read, e.g. `12. O-O`
+    SummaryTruncated,
+    /// contains `{}` as placeholder for the number of moves
+    SummaryMoves,
+    /// contains `{}` as placeholder for the accuracy in percent
+    SummaryAccuracy,
+    /// contains `{}` as placeholder for the number of moves of this class
+    SummaryInaccuracies,
+    /// contains `{}` as placeholder for the number of moves of this class
+    SummaryMistakes,
+    /// contains `{}` as placeholder for the number of moves of this class
+    SummaryBlunders,
+    // the classes of the annotated moves
+    AnnotationBest,
+    AnnotationGood,
+    AnnotationInaccuracy,
+    AnnotationMistake,
+    AnnotationBlunder,
+    /// contains `{}` as placeholder for the better line of the engine and its score
+    AnnotationBetter,
+}
+
+impl Language {
+    pub fn from_name(name: &str) -> Option<Language> {
+        // accept plain language codes as well as locales like `de_DE.UTF-8`
+        match name.to_lowercase().get(..2)? {
+            "en" => Some(Language::English),
+            "de" => Some(Language::German),
+            _ => None,
+        }
+    }
+
+    /// reads the language from the locale environment variables, falls back to english
+    pub fn detect() -> Language {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|var| env::var(var).ok())
+            .find(|value| !value.is_empty())
+            .and_then(|value| Language::from_name(&value))
+            .unwrap_or_default()
+    }
+}
+
+/// sets the language of all messages, only the first call has an effect
+pub fn set_language(language: Language) {
+    let _ = LANGUAGE.set(language);
+}
+
+/// translates a message into the selected language
+pub fn tr(message: Message) -> &'static str {
+    match LANGUAGE.get().copied().unwrap_or_default() {
+        Language::English => english(message),
+        Language::German => german(message),
+    }
+}
+
+fn english(message: Message) -> &'static str {
+    match message {
+        Message::Controls => "Controls:",
+        Message::ControlMove => "Move: {}",
+        Message::ControlSelect => "Select piece: {}",
+        Message::ControlMovePiece => "Move piece: direction keys; confirm with {}",
+        Message::ControlDeselect => "Deselect piece: {}",
+        Message::ControlFlip => "Flip board: {}",
+        Message::ControlTheme => "Change color theme: {}",
+        Message::ControlGoto => "Jump to square: {} followed by e.g. e4",
+        Message::ControlResign => "Resign: {}",
+        Message::ControlDraw => "Offer a draw: {}",
+        Message::ControlHint => "Show a hint: {}",
+        Message::ControlQuit => "Quit game: {}",
+        Message::Welcome => "Welcome to ",
+        Message::PlayerWon => "Player {} has won!",
+        Message::PressAnyKey => "Press any key to exit.",
+        Message::White => "WHITE",
+        Message::Black => "BLACK",
+        Message::TerminalTooSmall => "The terminal is too small, please enlarge it to at least {} characters.",
+        Message::LastMove => "Last move: {}",
+        Message::Check => "{} is in check",
+        Message::Thinking => "{} is thinking...",
+        Message::ConfirmResign => "Do you really want to resign? (y/n)",
+        Message::DrawOffered => "{} offers a draw. Accept? (y/n)",
+        Message::DrawDeclined => "The draw offer has been declined.",
+        Message::DrawAgreed => "The game ends in a draw.",
+        Message::WonByResignation => "Player {} has won by resignation!",
+        Message::WonOnTime => "Player {} has won on time!",
+        Message::WonByIllegalMove => "Player {} has won, the opponent played an illegal move!",
+        Message::AnalysisKeys => "Analysis: step through the moves with {}",
+        Message::AnalysisEditKey => "change a square with {}",
+        Message::AnalysisSideKey => "the side to move with {}",
+        Message::AnalysisDepth => "Depth {}",
+        Message::AnalysisTablebaseWin => "tablebase: win in {}",
+        Message::AnalysisTablebaseLoss => "tablebase: loss in {}",
+        Message::Hint => "Hint: {}",
+        Message::ConfirmAnnotate => "Analyse the game and save it annotated? (y/n)",
+        Message::Annotating => "Analysing position {}...",
+        Message::AnnotationSaved => "The annotated game has been saved to {}.",
+        Message::AnnotationFailed => "The annotated game could not be saved: {}",
+        Message::PuzzleInfo => "Puzzle {}",
+        Message::PuzzleRating => "Rating: {}",
+        Message::PuzzleStreak => "Streak: {}",
+        Message::PuzzleBestStreak => "(best {})",
+        Message::PuzzleToMove => "Find the best move for {}.",
+        Message::PuzzleCorrect => "Correct, keep going!",
+        Message::PuzzleSolved => "Solved!",
+        Message::PuzzleWrong => "Wrong, the solution was {}.",
+        Message::PuzzleNext => "Next puzzle: {}, quit: ESC",
+        Message::PuzzlesDone => "All puzzles have been played. Press any key to exit.",
+        Message::EndgameChoose => "{}: choose an endgame, ENTER: start, ESC: quit",
+        Message::EndgameComputing => "Computing the endgame tables...",
+        Message::EndgameOptimum => "Optimum: {} moves",
+        Message::EndgameWin => "Win the game",
+        Message::EndgameConverted => "Converted in {} moves,",
+        Message::EndgameOptimal => "the optimum!",
+        Message::EndgameSlower => "the optimum is {}.",
+        Message::EndgameNotConverted => "The position has not been converted.",
+        Message::EndgameNext => "Next position: {}, menu: ESC",
+        Message::MatchGame => "Game {}:",
+        Message::MatchEngine => "Engine",
+        Message::MatchScore => "Score",
+        Message::MatchWins => "Wins",
+        Message::MatchDraws => "Draws",
+        Message::MatchLosses => "Losses",
+        Message::MatchElo => "Elo difference: {} (95% confidence)",
+        Message::EvalTerm => "Term",
+        Message::EvalMaterial => "Material",
+        Message::EvalPieceSquares => "Piece squares",
+        Message::EvalPawnStructure => "Pawn structure",
+        Message::EvalKingSafety => "King safety",
+        Message::EvalMobility => "Mobility",
+        Message::EvalBishopPair => "Bishop pair",
+        Message::EvalRooksOnOpenFiles => "Rooks on open files",
+        Message::EvalTotal => "Total",
+        Message::EvalPhase => "Middlegame phase: {}",
+        Message::EvalScore => "Evaluation: {} cp for White",
+        Message::BookKey => "Polyglot key: {}",
+        Message::BookNotFound => "The position is not in the book.",
+        Message::BookMove => "Move",
+        Message::BookWeight => "Weight",
+        Message::BookShare => "Share",
+        Message::SummaryGame => "Game {}",
+        Message::SummaryTruncated => "truncated before {}, a move the rules of this game don't know",
+        Message::SummaryMoves => "{} moves",
+        Message::SummaryAccuracy => "accuracy {}",
+        Message::SummaryInaccuracies => "{} inaccuracies",
+        Message::SummaryMistakes => "{} mistakes",
+        Message::SummaryBlunders => "{} blunders",
+        Message::AnnotationBest => "Best",
+        Message::AnnotationGood => "Good",
+        Message::AnnotationInaccuracy => "Inaccuracy",
+        Message::AnnotationMistake => "Mistake",
+        Message::AnnotationBlunder => "Blunder",
+        Message::AnnotationBetter => "Better was {}",
+    }
+}
+
+fn german(message: Message) -> &'static str {
+    match message {
+        Message::Controls => "Bedienung:",
+        Message::ControlMove => "Bewegung: {}",
+        Message::ControlSelect => "Figur auswählen: {}",
+        Message::ControlMovePiece => "Figur Bewegen: Richtungstasten; Besätigung mit {}",
+        Message::ControlDeselect => "Figur abwählen: {}",
+        Message::ControlFlip => "Brett drehen: {}",
+        Message::ControlTheme => "Farbschema wechseln: {}",
+        Message::ControlGoto => "Feld anspringen: {} gefolgt von z.B. e4",
+        Message::ControlResign => "Aufgeben: {}",
+        Message::ControlDraw => "Remis anbieten: {}",
+        Message::ControlHint => "Tipp anzeigen: {}",
+        Message::ControlQuit => "Spiel beenden: {}",
+        Message::Welcome => "Willkommen zu ",
+        Message::PlayerWon => "Spieler {} hat gewonnen!",
+        Message::PressAnyKey => "Beliebige Taste zum Beenden drücken.",
+        Message::White => "WEIẞ",
+        Message::Black => "SCHWARZ",
+        Message::TerminalTooSmall => "Das Terminal ist zu klein, bitte auf mindestens {} Zeichen vergrößern.",
+        Message::LastMove => "Letzter Zug: {}",
+        Message::Check => "{} steht im Schach",
+        Message::Thinking => "{} denkt nach...",
+        Message::ConfirmResign => "Wirklich aufgeben? (j/n)",
+        Message::DrawOffered => "{} bietet Remis an. Annehmen? (j/n)",
+        Message::DrawDeclined => "Das Remisangebot wurde abgelehnt.",
+        Message::DrawAgreed => "Die Partie endet remis.",
+        Message::WonByResignation => "Spieler {} hat durch Aufgabe gewonnen!",
+        Message::WonOnTime => "Spieler {} hat auf Zeit gewonnen!",
+        Message::WonByIllegalMove => "Spieler {} hat gewonnen, der Gegner hat einen ungültigen Zug gespielt!",
+        Message::AnalysisKeys => "Analyse: mit {} durch die Züge blättern",
+        Message::AnalysisEditKey => "mit {} ein Feld ändern",
+        Message::AnalysisSideKey => "mit {} die Seite am Zug wechseln",
+        Message::AnalysisDepth => "Tiefe {}",
+        Message::AnalysisTablebaseWin => "Endspieldatenbank: Gewinn in {}",
+        Message::AnalysisTablebaseLoss => "Endspieldatenbank: Verlust in {}",
+        Message::Hint => "Tipp: {}",
+        Message::ConfirmAnnotate => "Partie analysieren und kommentiert speichern? (j/n)",
+        Message::Annotating => "Analysiere Stellung {}...",
+        Message::AnnotationSaved => "Die kommentierte Partie wurde in {} gespeichert.",
+        Message::AnnotationFailed => "Die kommentierte Partie konnte nicht gespeichert werden: {}",
+        Message::PuzzleInfo => "Aufgabe {}",
+        Message::PuzzleRating => "Wertung: {}",
+        Message::PuzzleStreak => "Serie: {}",
+        Message::PuzzleBestStreak => "(Rekord {})",
+        Message::PuzzleToMove => "Gesucht: der beste Zug für {}.",
+        Message::PuzzleCorrect => "Richtig, weiter so!",
+        Message::PuzzleSolved => "Gelöst!",
+        Message::PuzzleWrong => "Falsch, die Lösung war {}.",
+        Message::PuzzleNext => "Nächste Aufgabe: {}, beenden: ESC",
+        Message::PuzzlesDone => "Alle Aufgaben wurden gespielt. Beliebige Taste zum Beenden drücken.",
+        Message::EndgameChoose => "{}: Endspiel wählen, ENTER: starten, ESC: beenden",
+        Message::EndgameComputing => "Berechne die Endspieltabellen...",
+        Message::EndgameOptimum => "Optimum: {} Züge",
+        Message::EndgameWin => "Gewinne die Partie",
+        Message::EndgameConverted => "In {} Zügen verwertet,",
+        Message::EndgameOptimal => "das Optimum!",
+        Message::EndgameSlower => "das Optimum ist {}.",
+        Message::EndgameNotConverted => "Die Stellung wurde nicht verwertet.",
+        Message::EndgameNext => "Nächste Stellung: {}, Menü: ESC",
+        Message::MatchGame => "Partie {}:",
+        Message::MatchEngine => "Engine",
+        Message::MatchScore => "Punkte",
+        Message::MatchWins => "Siege",
+        Message::MatchDraws => "Remis",
+        Message::MatchLosses => "Niederlagen",
+        Message::MatchElo => "Elo-Differenz: {} (95 % Konfidenz)",
+        Message::EvalTerm => "Term",
+        Message::EvalMaterial => "Material",
+        Message::EvalPieceSquares => "Figurenfelder",
+        Message::EvalPawnStructure => "Bauernstruktur",
+        Message::EvalKingSafety => "Königssicherheit",
+        Message::EvalMobility => "Mobilität",
+        Message::EvalBishopPair => "Läuferpaar",
+        Message::EvalRooksOnOpenFiles => "Türme auf offenen Linien",
+        Message::EvalTotal => "Summe",
+        Message::EvalPhase => "Mittelspielphase: {}",
+        Message::EvalScore => "Bewertung: {} cp für Weiß",
+        Message::BookKey => "Polyglot-Schlüssel: {}",
+        Message::BookNotFound => "Die Stellung steht nicht im Buch.",
+        Message::BookMove => "Zug",
+        Message::BookWeight => "Gewicht",
+        Message::BookShare => "Anteil",
+        Message::SummaryGame => "Partie {}",
+        Message::SummaryTruncated => "abgebrochen vor {}, einem Zug, den die Regeln dieses Spiels nicht kennen",
+        Message::SummaryMoves => "{} Züge",
+        Message::SummaryAccuracy => "Genauigkeit {}",
+        Message::SummaryInaccuracies => "{} Ungenauigkeiten",
+        Message::SummaryMistakes => "{} Fehler",
+        Message::SummaryBlunders => "{} grobe Fehler",
+        Message::AnnotationBest => "Bester Zug",
+        Message::AnnotationGood => "Guter Zug",
+        Message::AnnotationInaccuracy => "Ungenauigkeit",
+        Message::AnnotationMistake => "Fehler",
+        Message::AnnotationBlunder => "Grober Fehler",
+        Message::AnnotationBetter => "Besser war {}",
+    }
+}
@@ -1,16 +1,55 @@
+pub mod analysis;
+pub mod annotate;
 pub mod board;
+pub mod book;
+pub mod clock;
+pub mod config;
 pub mod console;
+pub mod endgame;
+pub mod engine;
+pub mod eval;
+pub mod game;
+pub mod glyphs;
+pub mod human;
+pub mod i18n;
+pub mod network;
+pub mod notation;
 pub mod pieces;
+pub mod player;
+pub mod puzzle;
+pub mod random;
+pub mod renderer;
+pub mod retrograde;
+pub mod session;
+pub mod skill;
+pub mod syzygy;
+pub mod theme;
+pub mod time_manager;
+pub mod tournament;
+pub mod tt;
+pub mod uci;
+pub mod uci_server;
+pub mod ui;
 pub mod user_input;
+pub mod zobrist;
 
-use board::Board;
+use clock::Clock;
+use config::Config;
 use console::*;
-use pieces::{Piece, Position};
-use user_input::KeyPress;
+use game::Game;
+use i18n::{tr, Message};
+use pieces::{Color, Position};
+use player::Player;
+use renderer::{Renderer, TextRenderer};
+use session::TerminalSession;
+use ui::Ui;
 
 use std::cmp;
+use std::io::{self, IsTerminal};
+use std::path::Path;
+use std::sync::Arc;
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Default)]
 pub struct CursorPosition {
     col: i8,
     row: i8,
@@ -21,34 +60,36 @@ impl CursorPosition {
     const MIN_POSITION: i8 = 0;
 
     pub fn new() -> CursorPosition {
-        CursorPosition { col: 0, row: 0 }
+        CursorPosition::default()
+    }
+
+    pub fn jump_to(&mut self, position: Position) {
+        self.col = position.col;
+        self.row = position.row;
     }
 
     pub fn get_position(&self) -> (u16, u16) {
         (self.col as u16, self.row as u16)
     }
 
-    fn calc_manhattan_metrik(&mut self, move_set: &Vec<Position>) -> Vec<i8> {
+    fn calc_manhattan_metrik(&mut self, move_set: &[Position]) -> Vec<i8> {
         move_set
             .iter()
             .map(|c| (self.col - c.col).abs() + (self.row - c.row).abs())
             .collect()
     }
 
-    fn set_to_nearest_pos(&mut self, move_set: &Vec<Position>) {
-        let metrik_vec = self.calc_manhattan_metrik(&move_set);
-        match metrik_vec.iter().min() {
-            Some(m) => {
-                let min_index = metrik_vec.iter().position(|c| c == m).unwrap();
-                self.col = move_set.get(min_index).unwrap().col;
-                self.row = move_set.get(min_index).unwrap().row;
-            }
-            None => (),
+    fn set_to_nearest_pos(&mut self, move_set: &[Position]) {
+        let metrik_vec = self.calc_manhattan_metrik(move_set);
+        if let Some(m) = metrik_vec.iter().min() {
+            let min_index = metrik_vec.iter().position(|c| c == m).unwrap();
+            self.col = move_set.get(min_index).unwrap().col;
+            self.row = move_set.get(min_index).unwrap().row;
         }
     }
 
-    fn move_with_piece_up(&mut self, move_set: &Vec<Position>) {
-        let move_set_vec = move_set
+    fn move_with_piece_up(&mut self, move_set: &[Position]) {
+        let move_set_vec: Vec<Position> = move_set
             .iter()
             .filter(|c| c.row < self.row)
             .copied()
@@ -56,8 +97,8 @@ impl CursorPosition {
         self.set_to_nearest_pos(&move_set_vec);
     }
 
-    fn move_with_piece_down(&mut self, move_set: &Vec<Position>) {
-        let move_set_vec = move_set
+    fn move_with_piece_down(&mut self, move_set: &[Position]) {
+        let move_set_vec: Vec<Position> = move_set
             .iter()
             .filter(|c| c.row > self.row)
             .copied()
@@ -65,8 +106,8 @@ impl CursorPosition {
         self.set_to_nearest_pos(&move_set_vec);
     }
 
-    fn move_with_piece_left(&mut self, move_set: &Vec<Position>) {
-        let move_set_vec = move_set
+    fn move_with_piece_left(&mut self, move_set: &[Position]) {
+        let move_set_vec: Vec<Position> = move_set
             .iter()
             .filter(|c| c.col < self.col)
             .copied()
@@ -74,8 +115,8 @@ impl CursorPosition {
         self.set_to_nearest_pos(&move_set_vec);
     }
 
-    fn move_with_piece_right(&mut self, move_set: &Vec<Position>) {
-        let move_set_vec = move_set
+    fn move_with_piece_right(&mut self, move_set: &[Position]) {
+        let move_set_vec: Vec<Position> = move_set
             .iter()
             .filter(|c| c.col > self.col)
             .copied()
@@ -101,71 +142,145 @@ impl CursorPosition {
 }
 
 fn main() {
-    // init
-    let mut board: Board = Board::new();
-    board.initialize();
-    let mut cursor_pos = CursorPosition::new();
-    let mut selected_piece: Option<Piece> = None;
-    let mut current_player_is_white = true;
-
-    let mut move_set: Vec<Position> = vec![];
-
-    // init console output
-    match init_display(&board, &cursor_pos.get_position(), &move_set) {
-        Ok(()) => (),
-        Err(_) => (),
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    // subcommands which don't draw a board
+    let finished = match args.first().map(String::as_str) {
+        Some("match") => Some(tournament::run(&args[1..])),
+        Some("eval") => Some(eval::run(&args[1..])),
+        Some("book") => Some(book::run(&args[1..])),
+        Some("annotate") => Some(annotate::run(&args[1..])),
+        _ => None,
+    };
+    if let Some(result) = finished {
+        if let Err(e) = result {
+            eprintln!("{e}");
+            std::process::exit(2);
+        }
+        return;
+    }
+    // modes which take the usual options after their name
+    let mode = args.first().map(String::as_str).filter(|mode| matches!(*mode, "uci" | "analyse" | "puzzles" | "endgames"));
+    // the puzzle trainer expects the file of the puzzles before the options
+    let config_args = match mode {
+        Some("puzzles") => args.get(2..).unwrap_or_default(),
+        Some(_) => &args[1..],
+        None => &args,
+    };
+    let config = match Config::load(config_args) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(2);
+        }
     };
+    if mode == Some("uci") {
+        if let Err(e) = uci_server::run(&config) {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+        return;
+    }
+    i18n::set_language(config.language);
 
-    loop {
-        // expect user input
-        let Some(pressed_key) = user_input::await_user_input() else {
-            continue;
+    if mode == Some("analyse") {
+        let start = match &config.fen {
+            Some(fen) => Game::from_fen(fen, None).expect("the position has been checked with the config"),
+            None => Game::new(None),
         };
-
-        match (selected_piece, pressed_key) {
-            (None, KeyPress::Left) => cursor_pos.move_cursor_left(),
-            (None, KeyPress::Right) => cursor_pos.move_cursor_right(),
-            (None, KeyPress::Up) => cursor_pos.move_cursor_up(),
-            (None, KeyPress::Down) => cursor_pos.move_cursor_down(),
-            (None, KeyPress::Enter) => {
-                if let Some(p) = board[cursor_pos.into()] {
-                    if p.is_black() != current_player_is_white { // prevent white player form selecting black pieces and vice versa
-                        selected_piece = Some(p);
-                        move_set = p.get_available_moves(&board);
-                    }
-                }
+        // the tables are looked for before the terminal is taken over, so a wrong directory can be reported
+        let tablebases = match config.syzygy_path.as_deref().map(syzygy::Tablebases::open).transpose() {
+            Ok(tablebases) => tablebases.map(Arc::new),
+            Err(e) => {
+                eprintln!("{e}");
+                std::process::exit(2);
             }
-            (Some(_), KeyPress::Left) => cursor_pos.move_with_piece_left(&move_set),
-            (Some(_), KeyPress::Right) => cursor_pos.move_with_piece_right(&move_set),
-            (Some(_), KeyPress::Up) => cursor_pos.move_with_piece_up(&move_set),
-            (Some(_), KeyPress::Down) => cursor_pos.move_with_piece_down(&move_set),
-            (Some(p), KeyPress::Enter) => {
-                // prevent placing selected_piece on its own square
-                if p.position == cursor_pos.into() {
-                    continue;
-                }
-                match board.update_field(p.position, cursor_pos.into()) {
-                    Ok(_) => (),
-                    Err(_) => {
-                        let _ = display_winner(selected_piece.unwrap().color);
-                        break;
-                    },
-                };
-                selected_piece = None;
-                move_set = vec![];
-                current_player_is_white = !current_player_is_white;
+        };
+        let (_session, mut ui) = open_ui(&config, false);
+        analysis::run(&start, tablebases, &config, &mut ui);
+        return;
+    }
+
+    if mode == Some("puzzles") {
+        let puzzles = args.get(1).ok_or("expected the file of the puzzles".to_string()).and_then(|path| puzzle::load(Path::new(path)));
+        let puzzles = match puzzles {
+            Ok(puzzles) => puzzles,
+            Err(e) => {
+                eprintln!("{e}");
+                std::process::exit(2);
             }
-            (_, KeyPress::Esc) => return,
-            (_, KeyPress::BackSpace) => {
-                selected_piece = None;
-                move_set = vec![];
+        };
+        let (_session, mut ui) = open_ui(&config, false);
+        puzzle::run(&puzzles, &mut ui);
+        return;
+    }
+
+    if mode == Some("endgames") {
+        let (_session, mut ui) = open_ui(&config, false);
+        endgame::run(&config, &mut ui);
+        return;
+    }
+
+    // seat the players before the terminal is taken over, a network player may wait for a connection
+    let mut players: [Box<dyn Player>; 2] = [(&config.white, Color::White), (&config.black, Color::Black)].map(|(seat, color)| {
+        seat.create(color, &config).unwrap_or_else(|e| {
+            eprintln!("{e}");
+            std::process::exit(1);
+        })
+    });
+
+    // init
+    let mut game = Game::new(config.time_control.map(Clock::new));
+    // the board is turned towards a single player at this terminal, with auto flip always towards the player to move
+    let flipped = !players[0].is_local() && players[1].is_local();
+    let (_session, mut ui) = open_ui(&config, flipped);
+
+    let result = game::run(&mut game, &mut players, &mut ui, config.auto_flip);
+    if let Some(text) = result.describe() {
+        // the finished game can be annotated by the engine before the program exits
+        if !game.moves.is_empty() {
+            ui.set_message(Some(format!("{text} {}", tr(Message::ConfirmAnnotate))));
+            if user_input::await_confirmation() {
+                let names = [players[0].name(), players[1].name()];
+                let saved = annotate::save_game(&game, names, result, &config, &mut |position, positions| {
+                    ui.set_message(Some(tr(Message::Annotating).replace("{}", &format!("{position}/{positions}"))));
+                });
+                ui.set_message(Some(match saved {
+                    Ok(path) => tr(Message::AnnotationSaved).replace("{}", &path.display().to_string()),
+                    Err(e) => tr(Message::AnnotationFailed).replace("{}", &e.to_string()),
+                }));
+            } else {
+                ui.set_message(None);
             }
         }
-
-        // redraw board
-        match display_board(&board, &cursor_pos.get_position(), &move_set) {
-            Ok(()) => (),
-            Err(_) => (),
-        };
+        ui.game_over(&text);
+        user_input::await_any_key();
     }
 }
+
+/// takes over the terminal and picks the renderer, the terminal is restored when the session goes out of scope
+fn open_ui(config: &Config, flipped: bool) -> (TerminalSession, Ui) {
+    let settings = DisplaySettings {
+        flipped,
+        theme: config.display_theme(),
+        glyphs: config.glyphs,
+        color_overrides: config.theme_colors(),
+        key_bindings: config.key_bindings.clone(),
+    };
+
+    let use_text_renderer = config.text_renderer.unwrap_or(!io::stdout().is_terminal());
+    let session = if use_text_renderer { TerminalSession::start_raw() } else { TerminalSession::start() };
+    let session = match session {
+        Ok(session) => session,
+        Err(e) => {
+            eprintln!("could not set up the terminal: {e}");
+            std::process::exit(1);
+        }
+    };
+    let renderer: Box<dyn Renderer> = if use_text_renderer {
+        let line_ending = if io::stdout().is_terminal() { "\r\n" } else { "\n" };
+        Box::new(TextRenderer::new(io::stdout()).with_line_ending(line_ending))
+    } else {
+        Box::new(ConsoleRenderer::new(io::stdout()))
+    };
+    (session, Ui::new(renderer, settings, config.color_depth))
+}
@@ -0,0 +1,113 @@
+use crate::board::Move;
+use crate::game::Game;
+use crate::pieces::Color;
+use crate::player::{Action, GameEvent, Player};
+use crate::ui::Ui;
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+
+/// a player on another computer running rusty-chess, both games exchange their actions line by line:
+/// ```text
+/// seat black      sent once after connecting, the color of the remote player in the sender's game
+/// move e2e4
+/// draw            offers a draw, answered with accept or decline
+/// resign
+/// flag            the time of the sender has run out
+/// quit
+/// ```
+pub struct NetworkPlayer {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+}
+
+impl NetworkPlayer {
+    /// connects to a game waiting with `listen:<port>`
+    pub fn connect(address: &str, color: Color) -> io::Result<Self> {
+        NetworkPlayer::new(TcpStream::connect(address)?, color)
+    }
+
+    /// waits for the remote player to connect to the address, e.g. `127.0.0.1:7777`
+    pub fn listen(address: &str, color: Color) -> io::Result<Self> {
+        let listener = TcpListener::bind(address)?;
+        eprintln!("waiting for the {color} player to connect on {address}...");
+        let (stream, _) = listener.accept()?;
+        NetworkPlayer::new(stream, color)
+    }
+
+    /// both games have to seat the remote player at opposite colors
+    fn new(stream: TcpStream, color: Color) -> io::Result<Self> {
+        let mut player = NetworkPlayer {
+            reader: BufReader::new(stream.try_clone()?),
+            writer: stream,
+        };
+        player.send(&format!("seat {}", color_name(color)))?;
+        let line = player.receive()?;
+        if line != format!("seat {}", color_name(color.opponent())) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("the remote game does not seat its player as {color}: '{line}'"),
+            ));
+        }
+        Ok(player)
+    }
+
+    fn send(&mut self, line: &str) -> io::Result<()> {
+        writeln!(self.writer, "{line}")?;
+        self.writer.flush()
+    }
+
+    fn receive(&mut self) -> io::Result<String> {
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "the connection has been closed"));
+        }
+        Ok(line.trim().to_string())
+    }
+}
+
+impl Player for NetworkPlayer {
+    fn choose(&mut self, _game: &Game, _ui: &mut Ui) -> Action {
+        // a lost connection or an unknown line ends the game
+        let Ok(line) = self.receive() else {
+            return Action::Quit;
+        };
+        match line.split_once(' ') {
+            Some(("move", mv)) => Move::from_uci(mv).map(Action::Move).unwrap_or(Action::Quit),
+            _ => match line.as_str() {
+                "draw" => Action::OfferDraw,
+                "resign" => Action::Resign,
+                "flag" => Action::OutOfTime,
+                _ => Action::Quit,
+            },
+        }
+    }
+
+    fn accept_draw(&mut self, _game: &Game, _ui: &mut Ui) -> bool {
+        self.send("draw").is_ok() && self.receive().is_ok_and(|answer| answer == "accept")
+    }
+
+    fn notify(&mut self, event: GameEvent) {
+        let line = match event {
+            GameEvent::Moved(mv) => format!("move {}", mv.to_uci()),
+            GameEvent::Resigned => "resign".to_string(),
+            GameEvent::OutOfTime => "flag".to_string(),
+            GameEvent::DrawAnswered(true) => "accept".to_string(),
+            GameEvent::DrawAnswered(false) => "decline".to_string(),
+            GameEvent::Quit => "quit".to_string(),
+        };
+        // a lost connection is noticed when waiting for the next move
+        let _ = self.send(&line);
+    }
+
+    fn name(&self) -> String {
+        self.writer.peer_addr().map(|address| address.to_string()).unwrap_or_default()
+    }
+}
+
+fn color_name(color: Color) -> &'static str {
+    match color {
+        Color::White => "white",
+        Color::Black => "black",
+    }
+}
@@ -0,0 +1,288 @@
+use crate::board::{Board, Move};
+use crate::game::{Game, GameResult};
+use crate::pieces::{Color, PieceVariant, Position};
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// writes a move in standard algebraic notation, e.g. `Nf3`, `exd5`, `Rad1` or `e8=Q+`
+/// the move has to be played by the piece on its origin square of the given board
+pub fn to_san(board: &Board, mv: Move) -> String {
+    let Some(piece) = board[mv.from] else {
+        return mv.to_uci();
+    };
+    let capture = board[mv.to].is_some();
+    let mut san = String::new();
+
+    if piece.variant == PieceVariant::Pawn {
+        if capture {
+            san.push(mv.from.to_square_name().remove(0));
+        }
+    } else {
+        san.push(piece.variant.letter());
+        // name the file, the rank or both if another piece of the same kind can reach the square
+        let rivals: Vec<Position> = board
+            .moves(piece.color)
+            .into_iter()
+            .filter(|other| other.to == mv.to && other.from != mv.from)
+            .filter(|other| board[other.from].is_some_and(|p| p.variant == piece.variant))
+            .map(|other| other.from)
+            .collect();
+        let from = mv.from.to_square_name();
+        if !rivals.is_empty() {
+            if rivals.iter().all(|other| other.col != mv.from.col) {
+                san.push_str(&from[..1]);
+            } else if rivals.iter().all(|other| other.row != mv.from.row) {
+                san.push_str(&from[1..]);
+            } else {
+                san.push_str(&from);
+            }
+        }
+    }
+    if capture {
+        san.push('x');
+    }
+    san.push_str(&mv.to.to_square_name());
+    if let Some(variant) = mv.promotion {
+        san.push('=');
+        san.push(variant.letter());
+    }
+
+    let mut after = board.clone();
+    if after.play(mv).is_ok() && after.is_in_check(piece.color.opponent()) {
+        san.push('+');
+    }
+    san
+}
+
+/// writes a line of moves in standard algebraic notation with move numbers, e.g. `12... Nf6 13. e5 Nd5`
+/// the line ends with a captured king
+pub fn line_to_san(board: &Board, color: Color, fullmove_number: u32, moves: &[Move]) -> String {
+    let mut board = board.clone();
+    let (mut color, mut number) = (color, fullmove_number);
+    let mut words = vec![];
+    for (i, mv) in moves.iter().enumerate() {
+        if color == Color::White {
+            words.push(format!("{number}."));
+        } else if i == 0 {
+            words.push(format!("{number}..."));
+        }
+        words.push(to_san(&board, *mv));
+        if board.play(*mv).is_err() {
+            break;
+        }
+        if color == Color::Black {
+            number += 1;
+        }
+        color = color.opponent();
+    }
+    words.join(" ")
+}
+
+/// finds the move of the given color written in standard algebraic notation, None if there is no such move
+/// castling is not part of the rules of this game, so `O-O` is never found
+pub fn parse_san(board: &Board, color: Color, san: &str) -> Option<Move> {
+    let san = san.trim_end_matches(['+', '#', '!', '?']);
+    let (san, promotion) = match san.split_once('=') {
+        Some((san, piece)) => (san, Some(PieceVariant::from_letter(piece.chars().next()?)?)),
+        None => match san.chars().last() {
+            Some(c) if c.is_ascii_uppercase() && san.len() > 2 => (&san[..san.len() - 1], PieceVariant::from_letter(c)),
+            _ => (san, None),
+        },
+    };
+    let to = Position::from_square_name(san.get(san.len().checked_sub(2)?..)?)?;
+    let prefix = &san[..san.len() - 2];
+    let (variant, hint) = match prefix.chars().next() {
+        Some(c) if c.is_ascii_uppercase() => (PieceVariant::from_letter(c)?, &prefix[1..]),
+        _ => (PieceVariant::Pawn, prefix),
+    };
+    let hint: Vec<char> = hint.chars().filter(|c| *c != 'x').collect();
+
+    let candidates: Vec<Move> = board
+        .moves(color)
+        .into_iter()
+        .filter(|mv| mv.to == to && board[mv.from].is_some_and(|p| p.variant == variant))
+        .filter(|mv| mv.promotion == promotion || promotion.is_none() && mv.promotion == Some(PieceVariant::Queen))
+        .filter(|mv| {
+            let from = mv.from.to_square_name();
+            hint.iter().all(|c| from.contains(*c))
+        })
+        .collect();
+    match candidates.as_slice() {
+        [mv] => Some(*mv),
+        _ => None,
+    }
+}
+
+/// a game read from a PGN file, the moves are still in standard algebraic notation
+#[derive(Clone, Debug, Default)]
+pub struct PgnGame {
+    pub tags: Vec<(String, String)>,
+    pub moves: Vec<String>,
+}
+
+impl PgnGame {
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags.iter().find(|(key, _)| key == name).map(|(_, value)| value.as_str())
+    }
+}
+
+/// reads all games of a PGN file, comments, variations and annotations are skipped
+pub fn parse_pgn(text: &str) -> Vec<PgnGame> {
+    let mut games = vec![];
+    let mut game = PgnGame::default();
+    let mut chars = text.chars();
+    let mut token = String::new();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '[' => {
+                finish_token(&mut token, &mut game, &mut games);
+                // tags after moves start the next game, even if the last one has no result
+                if !game.moves.is_empty() {
+                    games.push(std::mem::take(&mut game));
+                }
+                let tag: String = chars.by_ref().take_while(|c| *c != ']').collect();
+                if let Some((key, value)) = tag.split_once(' ') {
+                    game.tags.push((key.to_string(), value.trim().trim_matches('"').to_string()));
+                }
+            }
+            '{' => {
+                chars.by_ref().take_while(|c| *c != '}').for_each(drop);
+            }
+            ';' => {
+                chars.by_ref().take_while(|c| *c != '\n').for_each(drop);
+            }
+            '(' => {
+                let mut depth = 1;
+                for c in chars.by_ref() {
+                    match c {
+                        '(' => depth += 1,
+                        ')' => depth -= 1,
+                        _ => (),
+                    }
+                    if depth == 0 {
+                        break;
+                    }
+                }
+            }
+            c if c.is_whitespace() => finish_token(&mut token, &mut game, &mut games),
+            c => token.push(c),
+        }
+    }
+    finish_token(&mut token, &mut game, &mut games);
+    if !game.moves.is_empty() {
+        games.push(game);
+    }
+    games
+}
+
+/// ends the current token of the movetext, which is a move, a move number, an annotation or a result
+fn finish_token(token: &mut String, game: &mut PgnGame, games: &mut Vec<PgnGame>) {
+    // results start with digits as well
+    let word = match token.as_str() {
+        "1-0" | "0-1" | "1/2-1/2" | "*" => {
+            games.push(std::mem::take(game));
+            ""
+        }
+        token => token.trim_start_matches(|c: char| c.is_ascii_digit() || c == '.'),
+    };
+    if !word.is_empty() && !word.starts_with('$') {
+        game.moves.push(word.to_string());
+    }
+    token.clear();
+}
+
+/// writes a finished game in PGN, the tags Event, Site, Date, Round, White and Black have to be given
+pub fn write_pgn(game: &Game, tags: &[(&str, String)], result: GameResult) -> String {
+    write_annotated_pgn(game, tags, result.pgn(), result.describe().as_deref(), &[])
+}
+
+/// what an annotator has to say about a single move
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct MoveNote {
+    /// a numeric annotation glyph, e.g. 2 for `?` or 4 for `??`
+    pub nag: Option<u8>,
+    pub comment: Option<String>,
+}
+
+/// writes a game in PGN with notes after its moves, the result is given in the notation of PGN, e.g. `1-0`,
+/// and explained by a comment after the last move
+pub fn write_annotated_pgn(
+    game: &Game,
+    tags: &[(&str, String)],
+    result: &str,
+    result_comment: Option<&str>,
+    notes: &[MoveNote],
+) -> String {
+    let mut pgn = String::new();
+    for (key, value) in tags {
+        pgn.push_str(&format!("[{key} \"{}\"]\n", value.replace('"', "'")));
+    }
+    pgn.push_str(&format!("[Result \"{result}\"]\n"));
+    let mut replay = Game::from_fen(&game.start_fen, None).expect("the start position has been exported as FEN");
+    if game.start_fen != Game::new(None).start_fen {
+        pgn.push_str(&format!("[SetUp \"1\"]\n[FEN \"{}\"]\n", game.start_fen));
+    }
+    pgn.push('\n');
+
+    // comments are split into words, so the lines can be wrapped inside them
+    let comment_words = |text: &str| {
+        let text = format!("{{{}}}", text.replace('}', ")"));
+        text.split(' ').map(str::to_string).collect::<Vec<_>>()
+    };
+    let mut words = vec![];
+    for (i, mv) in game.moves.iter().enumerate() {
+        let note = notes.get(i);
+        if replay.side_to_move == Color::White {
+            words.push(format!("{}.", replay.fullmove_number));
+        } else if i == 0 || notes.get(i - 1).is_some_and(|note| note.comment.is_some()) {
+            // a move of black after a comment is numbered again
+            words.push(format!("{}...", replay.fullmove_number));
+        }
+        words.push(to_san(&replay.board, *mv));
+        if let Some(nag) = note.and_then(|note| note.nag) {
+            words.push(format!("${nag}"));
+        }
+        if let Some(comment) = note.and_then(|note| note.comment.as_deref()) {
+            words.extend(comment_words(comment));
+        }
+        let _ = replay.play(*mv);
+    }
+    if let Some(text) = result_comment {
+        words.extend(comment_words(text));
+    }
+    words.push(result.to_string());
+
+    // lines of movetext are at most 80 characters long
+    let mut line = String::new();
+    for word in words {
+        if !line.is_empty() && line.len() + word.len() + 1 > 80 {
+            pgn.push_str(&line);
+            pgn.push('\n');
+            line.clear();
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(&word);
+    }
+    pgn.push_str(&line);
+    pgn.push_str("\n\n");
+    pgn
+}
+
+/// today's date in the format of the PGN Date tag, e.g. `2024.03.17`
+pub fn pgn_date() -> String {
+    let days = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() / 86_400).unwrap_or(0) as i64;
+    // converts days since 1970-01-01 into a date of the gregorian calendar
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{year:04}.{month:02}.{day:02}")
+}
@@ -6,6 +6,7 @@ use crate::pieces::Direction::{
     NorthWest, South, SouthEast, SouthSouthEast, SouthSouthWest, SouthWest, West, WestNorthWest,
     WestSouthWest,
 };
+use crate::i18n::{tr, Message};
 use crate::{board::Board, CursorPosition};
 
 #[derive(Clone, Copy, PartialEq, Debug)]
@@ -14,13 +15,22 @@ pub enum Color {
     Black,
 }
 
+impl Color {
+    pub fn opponent(self) -> Color {
+        match self {
+            Color::White => Color::Black,
+            Color::Black => Color::White,
+        }
+    }
+}
+
 impl Display for Color {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{:?}", match self {Color::Black => "SCHWARZ", Color::White => "WEIẞ",})
+        write!(f, "{}", match self {Color::Black => tr(Message::Black), Color::White => tr(Message::White),})
     }
 }
 
-#[derive(Copy, Clone, PartialEq)]
+#[derive(Copy, Clone, PartialEq, Debug)]
 pub enum PieceVariant {
     Pawn = 0x2659,
     Knight = 0x2658,
@@ -49,7 +59,7 @@ enum Direction {
     NorthNorthWest,
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug)]
 pub struct Position {
     pub col: i8,
     pub row: i8,
@@ -209,7 +219,7 @@ impl Piece {
 
     /// transform a pawn into a desired piece_var if it reached the 0th or 7th row in the array
     pub(crate) fn promote(&mut self, piece_variant: PieceVariant) -> Result<PieceVariant, &str> {
-        if self.color == Color::White && self.position.col == 0 || self.color == Color::Black && self.position.col == 7 {
+        if self.variant == PieceVariant::Pawn && self.promotes_on(self.position) {
             self.variant = piece_variant;
             Ok(piece_variant)
         } else {
@@ -217,6 +227,12 @@ impl Piece {
         }
     }
 
+    /// whether a pawn moving onto the given square reaches the last rank
+    pub fn promotes_on(self, to: Position) -> bool {
+        self.variant == PieceVariant::Pawn
+            && (self.color == Color::White && to.row == 0 || self.color == Color::Black && to.row == 7)
+    }
+
     pub(crate) fn get_figure(self) -> u32 {
         match self.color {
             Color::Black => self.variant.get_figure() + 6,
@@ -227,9 +243,43 @@ impl Piece {
     pub fn is_black(self) -> bool {
         matches!(self.color, Color::Black)
     }
+
+    /// the letter of the piece in FEN, upper case for white and lower case for black
+    pub fn fen_char(self) -> char {
+        let letter = self.variant.letter();
+        match self.color {
+            Color::White => letter,
+            Color::Black => letter.to_ascii_lowercase(),
+        }
+    }
 }
 
 impl PieceVariant {
+    /// the upper case letter of the piece in the algebraic notation
+    pub fn letter(self) -> char {
+        match self {
+            PieceVariant::Pawn => 'P',
+            PieceVariant::Knight => 'N',
+            PieceVariant::Bishop => 'B',
+            PieceVariant::Rook => 'R',
+            PieceVariant::Queen => 'Q',
+            PieceVariant::King => 'K',
+        }
+    }
+
+    /// the piece of a letter in any case, e.g. `q` or `Q`
+    pub fn from_letter(letter: char) -> Option<PieceVariant> {
+        match letter.to_ascii_uppercase() {
+            'P' => Some(PieceVariant::Pawn),
+            'N' => Some(PieceVariant::Knight),
+            'B' => Some(PieceVariant::Bishop),
+            'R' => Some(PieceVariant::Rook),
+            'Q' => Some(PieceVariant::Queen),
+            'K' => Some(PieceVariant::King),
+            _ => None,
+        }
+    }
+
     pub(crate) fn get_figure(self) -> u32 {
         self as u32
     }
@@ -239,6 +289,22 @@ impl Position {
     pub fn new(col: i8, row: i8) -> Self {
         Self { col, row }
     }
+
+    /// parses a square name like `e4`, row 0 of the board array is the 8th rank
+    pub fn from_square_name(name: &str) -> Option<Position> {
+        let mut chars = name.chars();
+        let file = chars.next()?.to_ascii_lowercase();
+        let rank = chars.next()?;
+        if chars.next().is_some() || !('a'..='h').contains(&file) || !('1'..='8').contains(&rank) {
+            return None;
+        }
+        Some(Position::new(file as i8 - 'a' as i8, '8' as i8 - rank as i8))
+    }
+
+    /// returns the name of the square, e.g. `e4`
+    pub fn to_square_name(self) -> String {
+        format!("{}{}", (b'a' + self.col as u8) as char, 8 - self.row)
+    }
 }
 
 // Helper functions
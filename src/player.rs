@@ -0,0 +1,126 @@
+use crate::board::Move;
+use crate::book::Book;
+use crate::config::Config;
+use crate::engine::{Engine, EnginePlayer};
+use crate::game::Game;
+use crate::human::HumanPlayer;
+use crate::network::NetworkPlayer;
+use crate::pieces::Color;
+use crate::syzygy::Tablebases;
+use crate::uci::UciPlayer;
+use crate::ui::Ui;
+
+use std::io;
+use std::sync::Arc;
+
+/// what a player does on its turn
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Action {
+    Move(Move),
+    Resign,
+    /// the opponent is asked whether to accept, afterwards the player has to move anyway
+    OfferDraw,
+    /// the player noticed that its time has run out
+    OutOfTime,
+    /// the game is ended without a result, e.g. by pressing ESC
+    Quit,
+}
+
+/// what the opponent did, players outside of this process need to be told about it
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum GameEvent {
+    Moved(Move),
+    Resigned,
+    OutOfTime,
+    /// the answer of the opponent to a draw offer of the player
+    DrawAnswered(bool),
+    Quit,
+}
+
+/// a participant of the game, given the position and the clock it produces a move or another action
+pub trait Player {
+    /// called on the turn of the player, the clock of the game is already running
+    fn choose(&mut self, game: &Game, ui: &mut Ui) -> Action;
+
+    /// asks the player whether to accept the draw offered by the opponent
+    fn accept_draw(&mut self, game: &Game, ui: &mut Ui) -> bool;
+
+    /// informs the player about an action of the opponent
+    fn notify(&mut self, _event: GameEvent) {}
+
+    /// prepares the player for the next game of a match
+    fn new_game(&mut self) {}
+
+    /// the name written into the PGN of a game
+    fn name(&self) -> String;
+
+    /// whether the player sits in front of this terminal
+    fn is_local(&self) -> bool {
+        false
+    }
+}
+
+/// who sits at one side of the board, given as `white`/`black` in the config or `--white`/`--black`
+#[derive(Clone, PartialEq, Debug, Default)]
+pub enum Seat {
+    /// a player at this keyboard
+    #[default]
+    Human,
+    /// the built-in engine
+    Engine,
+    /// an external engine speaking UCI, e.g. `uci:/usr/bin/stockfish`, plain `uci` starts the configured engine
+    Uci(Option<String>),
+    /// a player on another computer which listens for the connection, e.g. `connect:192.168.0.2:7777`
+    Connect(String),
+    /// waits for a player on another computer to connect at the address, e.g. `listen:0.0.0.0:7777`, a port alone
+    /// only accepts connections from this computer
+    Listen(String),
+}
+
+impl Seat {
+    pub fn from_name(name: &str) -> Option<Seat> {
+        match name.split_once(':') {
+            None if name == "human" => Some(Seat::Human),
+            None if name == "engine" => Some(Seat::Engine),
+            None if name == "uci" => Some(Seat::Uci(None)),
+            Some(("uci", path)) if !path.is_empty() => Some(Seat::Uci(Some(path.to_string()))),
+            Some(("connect", address)) if !address.is_empty() => Some(Seat::Connect(address.to_string())),
+            Some(("listen", address)) => match address.rsplit_once(':') {
+                None => address.parse::<u16>().ok().map(|port| Seat::Listen(format!("127.0.0.1:{port}"))),
+                Some((host, port)) if !host.is_empty() && port.parse::<u16>().is_ok() => Some(Seat::Listen(address.to_string())),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// starts the player for the given color, external engines are spawned and network peers connected here
+    pub fn create(&self, color: Color, config: &Config) -> io::Result<Box<dyn Player>> {
+        Ok(match self {
+            Seat::Human => {
+                let mut human = HumanPlayer::new(color, config.key_bindings.clone());
+                human.hint_cursor = config.hint_cursor;
+                human.hint_hash = config.engine_hash;
+                human.hint_threads = config.engine_threads;
+                Box::new(human)
+            }
+            Seat::Engine => {
+                let book = config.book.as_deref().map(Book::open).transpose()?;
+                let mut engine = Engine::new(config.engine_depth, config.engine_hash);
+                engine.threads = config.engine_threads;
+                engine.skill = config.engine_skill;
+                engine.tablebases = config.syzygy_path.as_deref().map(Tablebases::open).transpose()?.map(Arc::new);
+                Box::new(EnginePlayer::new(engine, book))
+            }
+            Seat::Uci(path) => {
+                let path = path.as_ref().or(config.uci_engine.as_ref()).ok_or(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    "no UCI engine configured, use uci:<path> or set uci_engine",
+                ))?;
+                Box::new(UciPlayer::start(path, &config.uci_options, config.engine_depth)?)
+            }
+            Seat::Connect(address) => Box::new(NetworkPlayer::connect(address, color)?),
+            Seat::Listen(address) => Box::new(NetworkPlayer::listen(address, color)?),
+        })
+    }
+}
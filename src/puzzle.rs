@@ -0,0 +1,325 @@
+use crate::board::{Board, Move};
+use crate::config::Config;
+use crate::game::Game;
+use crate::human::{Input, MoveInput};
+use crate::i18n::{tr, Message};
+use crate::notation;
+use crate::pieces::Color;
+use crate::random::Random;
+use crate::ui::Ui;
+use crate::user_input::{self, KeyBindings, KeyPress};
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+// the rating of a player without any solved puzzles and of puzzles without a rating
+const START_RATING: i32 = 1500;
+const MIN_RATING: i32 = 100;
+// how far a single result moves the rating of the player, like the K-factor of Elo ratings
+const RATING_FACTOR: f64 = 32.0;
+// the opponent waits a moment before replying, so the player sees the own move first
+const REPLY_DELAY: Duration = Duration::from_millis(600);
+// the next puzzle is picked at random among this many puzzles closest to the rating of the player
+const CANDIDATES: usize = 10;
+const PROGRESS_FILE_NAME: &str = "puzzles";
+
+/// a position in which the player has to find the moves of the solution
+#[derive(Clone, Debug)]
+pub struct Puzzle {
+    pub id: String,
+    /// the position before the opponent's move which starts the puzzle
+    pub fen: String,
+    /// the opponent's first move, then the moves of the player and the opponent's replies in turn
+    pub moves: Vec<Move>,
+    pub rating: i32,
+    pub themes: Vec<String>,
+}
+
+/// where the fields of a puzzle are found in a line of the file
+struct Columns {
+    id: Option<usize>,
+    fen: usize,
+    moves: usize,
+    rating: Option<usize>,
+    themes: Option<usize>,
+}
+
+impl Columns {
+    /// the columns of the puzzle database of lichess: `PuzzleId,FEN,Moves,Rating,RatingDeviation,Popularity,
+    /// NbPlays,Themes,GameUrl,OpeningTags`
+    fn lichess() -> Self {
+        Columns { id: Some(0), fen: 1, moves: 2, rating: Some(3), themes: Some(7) }
+    }
+
+    /// the columns named in a header line, None if the line is no header
+    fn from_header(line: &str) -> Option<Self> {
+        let names: Vec<String> = line.split(',').map(|name| name.trim().to_lowercase()).collect();
+        let column = |name: &str| names.iter().position(|n| n == name);
+        Some(Columns {
+            id: column("puzzleid").or(column("id")),
+            fen: column("fen")?,
+            moves: column("moves")?,
+            rating: column("rating"),
+            themes: column("themes"),
+        })
+    }
+}
+
+/// reads the puzzles of a CSV file, either with a header naming the columns `FEN` and `Moves` (and optionally
+/// `PuzzleId`, `Rating` and `Themes`) or in the column order of the lichess puzzle database
+///
+/// the moves are given in UCI notation separated by spaces, the first one is played by the opponent.
+/// puzzles which need moves the rules of this game don't know, e.g. castling, are left out
+pub fn load(path: &Path) -> Result<Vec<Puzzle>, String> {
+    let content = fs::read_to_string(path).map_err(|e| format!("could not read {}: {e}", path.display()))?;
+    let mut lines = content.lines().filter(|line| !line.trim().is_empty() && !line.starts_with('#')).peekable();
+    let columns = match lines.peek().and_then(|line| Columns::from_header(line)) {
+        Some(columns) => {
+            lines.next();
+            columns
+        }
+        None => Columns::lichess(),
+    };
+    let puzzles: Vec<Puzzle> = lines.enumerate().filter_map(|(i, line)| parse_puzzle(line, &columns, i + 1)).collect();
+    if puzzles.is_empty() {
+        return Err(format!("{} contains no puzzles which can be played", path.display()));
+    }
+    Ok(puzzles)
+}
+
+fn parse_puzzle(line: &str, columns: &Columns, number: usize) -> Option<Puzzle> {
+    let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+    let field = |index: Option<usize>| index.and_then(|index| fields.get(index)).copied().filter(|field| !field.is_empty());
+    let fen = field(Some(columns.fen))?.to_string();
+    let moves = field(Some(columns.moves))?.split_whitespace().map(Move::from_uci).collect::<Option<Vec<Move>>>()?;
+    // the opponent's move and at least one move of the player
+    if moves.len() < 2 {
+        return None;
+    }
+    let mut game = Game::from_fen(&fen, None).ok()?;
+    for mv in &moves {
+        if !game.board.moves(game.side_to_move).contains(mv) {
+            return None;
+        }
+        let _ = game.play(*mv);
+    }
+    Some(Puzzle {
+        id: field(columns.id).map(str::to_string).unwrap_or(number.to_string()),
+        fen,
+        moves,
+        rating: field(columns.rating).and_then(|rating| rating.parse().ok()).unwrap_or(START_RATING),
+        themes: field(columns.themes).map(|themes| themes.split_whitespace().map(str::to_string).collect()).unwrap_or_default(),
+    })
+}
+
+/// the rating and the streaks of the player, kept in a file beside the config file
+#[derive(Clone, PartialEq, Debug)]
+pub struct Progress {
+    pub rating: i32,
+    /// puzzles solved in a row
+    pub streak: u32,
+    pub best_streak: u32,
+    pub solved: u32,
+    pub failed: u32,
+}
+
+impl Default for Progress {
+    fn default() -> Self {
+        Progress { rating: START_RATING, streak: 0, best_streak: 0, solved: 0, failed: 0 }
+    }
+}
+
+impl Progress {
+    /// location of the progress file, in the directory of the default config file
+    pub fn default_path() -> Option<PathBuf> {
+        Config::default_path()?.parent().map(|dir| dir.join(PROGRESS_FILE_NAME))
+    }
+
+    /// reads the progress saved as `key = value` lines, a missing file starts from scratch
+    pub fn load(path: &Path) -> Self {
+        let mut progress = Progress::default();
+        let Ok(content) = fs::read_to_string(path) else {
+            return progress;
+        };
+        for (key, value) in content.lines().filter_map(|line| line.split_once('=')) {
+            let Ok(value) = value.trim().parse::<i32>() else {
+                continue;
+            };
+            match key.trim() {
+                "rating" => progress.rating = value.max(MIN_RATING),
+                "streak" => progress.streak = value.max(0) as u32,
+                "best_streak" => progress.best_streak = value.max(0) as u32,
+                "solved" => progress.solved = value.max(0) as u32,
+                "failed" => progress.failed = value.max(0) as u32,
+                _ => (),
+            }
+        }
+        progress
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let content = format!(
+            "rating = {}\nstreak = {}\nbest_streak = {}\nsolved = {}\nfailed = {}\n",
+            self.rating, self.streak, self.best_streak, self.solved, self.failed
+        );
+        fs::write(path, content)
+    }
+
+    /// updates the rating like an Elo rating after a game against the puzzle, returns the change
+    pub fn record(&mut self, puzzle_rating: i32, solved: bool) -> i32 {
+        let expected = 1.0 / (1.0 + 10f64.powf((puzzle_rating - self.rating) as f64 / 400.0));
+        let score = if solved { 1.0 } else { 0.0 };
+        let old_rating = self.rating;
+        self.rating = (self.rating + (RATING_FACTOR * (score - expected)).round() as i32).max(MIN_RATING);
+        if solved {
+            self.solved += 1;
+            self.streak += 1;
+            self.best_streak = self.best_streak.max(self.streak);
+        } else {
+            self.failed += 1;
+            self.streak = 0;
+        }
+        self.rating - old_rating
+    }
+}
+
+/// the puzzle trainer, started with `rusty-chess puzzles <file>`: the opponent's first move is played, then
+/// the player at this terminal enters the moves of the solution while the opponent's replies follow automatically
+pub fn run(puzzles: &[Puzzle], ui: &mut Ui) {
+    let path = Progress::default_path();
+    let mut progress = path.as_deref().map(Progress::load).unwrap_or_default();
+    let bindings = ui.settings.key_bindings.clone();
+    let mut random = Random::from_time();
+    let mut played = vec![false; puzzles.len()];
+
+    while let Some(index) = pick(puzzles, &played, progress.rating, &mut random) {
+        played[index] = true;
+        if !play(&puzzles[index], &mut progress, path.as_deref(), &bindings, ui) {
+            return;
+        }
+    }
+    ui.set_message(Some(tr(Message::PuzzlesDone).to_string()));
+    user_input::await_any_key();
+}
+
+/// one of the puzzles not played yet whose rating is close to the one of the player
+fn pick(puzzles: &[Puzzle], played: &[bool], rating: i32, random: &mut Random) -> Option<usize> {
+    let mut candidates: Vec<usize> = (0..puzzles.len()).filter(|i| !played[*i]).collect();
+    candidates.sort_by_key(|i| (puzzles[*i].rating - rating).abs());
+    candidates.truncate(CANDIDATES);
+    (!candidates.is_empty()).then(|| candidates[random.below(candidates.len() as u64) as usize])
+}
+
+/// lets the player solve a puzzle and records the result, returns false if the player quits
+fn play(puzzle: &Puzzle, progress: &mut Progress, path: Option<&Path>, bindings: &KeyBindings, ui: &mut Ui) -> bool {
+    let mut game = Game::from_fen(&puzzle.fen, None).expect("the position has been checked when loading");
+    let color = game.side_to_move.opponent();
+    let mut input = MoveInput::default();
+    input.reset(ui);
+    ui.hint = None;
+    ui.set_header(Some(header(puzzle, progress, false)));
+    ui.set_message(None);
+    ui.settings.flipped = color == Color::Black;
+    ui.init(&game.board);
+    thread::sleep(REPLY_DELAY);
+    let _ = game.play(puzzle.moves[0]);
+    let mut next = 1;
+    ui.set_message(Some(tr(Message::PuzzleToMove).replace("{}", &color.to_string())));
+    ui.redraw(&game.board);
+
+    let solved = loop {
+        let Some(pressed_key) = user_input::await_user_input(bindings) else {
+            continue;
+        };
+        match input.handle(pressed_key, &game.board, color, ui) {
+            Input::Move(mv) => {
+                let expected = puzzle.moves[next];
+                // the promotion is taken from the solution, any other mate is as good as the one of the solution
+                let mv = if (mv.from, mv.to) == (expected.from, expected.to) {
+                    expected
+                } else if next + 1 == puzzle.moves.len() && is_mate(&game.board, mv, color) {
+                    mv
+                } else {
+                    break false;
+                };
+                let _ = game.play(mv);
+                next += 1;
+                ui.redraw(&game.board);
+                if next == puzzle.moves.len() {
+                    break true;
+                }
+                ui.set_message(Some(tr(Message::PuzzleCorrect).to_string()));
+                thread::sleep(REPLY_DELAY);
+                let _ = game.play(puzzle.moves[next]);
+                next += 1;
+                ui.redraw(&game.board);
+            }
+            // asking for the solution gives the puzzle up
+            Input::Key(KeyPress::Hint) => break false,
+            Input::Key(KeyPress::Esc) => return false,
+            Input::Key(_) | Input::Handled => (),
+        }
+    };
+
+    let change = progress.record(puzzle.rating, solved);
+    if let Some(path) = path {
+        // the trainer goes on without saving, e.g. in a read-only home directory
+        let _ = progress.save(path);
+    }
+    let result = if solved {
+        tr(Message::PuzzleSolved).to_string()
+    } else {
+        let solution = puzzle.moves[next];
+        ui.hint = Some(solution);
+        tr(Message::PuzzleWrong).replace("{}", &notation::to_san(&game.board, solution))
+    };
+    ui.set_header(Some(header(puzzle, progress, true)));
+    ui.redraw(&game.board);
+    let keys = tr(Message::PuzzleNext).replace("{}", &bindings.describe(KeyPress::Enter));
+    ui.set_message(Some(format!("{result} ({change:+})  {keys}")));
+    loop {
+        match user_input::await_user_input(bindings) {
+            Some(KeyPress::Enter) => return true,
+            Some(KeyPress::Esc) => return false,
+            Some(KeyPress::Resize) => ui.init(&game.board),
+            _ => (),
+        }
+    }
+}
+
+/// the puzzle and the rating and streaks of the player, the themes would give the solution away until it is finished
+fn header(puzzle: &Puzzle, progress: &Progress, finished: bool) -> String {
+    let mut header = [
+        tr(Message::PuzzleInfo).replace("{}", &format!("{} ({})", puzzle.id, puzzle.rating)),
+        tr(Message::PuzzleRating).replace("{}", &progress.rating.to_string()),
+        format!(
+            "{} {}",
+            tr(Message::PuzzleStreak).replace("{}", &progress.streak.to_string()),
+            tr(Message::PuzzleBestStreak).replace("{}", &progress.best_streak.to_string())
+        ),
+    ]
+    .join("  ");
+    if finished && !puzzle.themes.is_empty() {
+        header.push_str(&format!("  {}", puzzle.themes.join(", ")));
+    }
+    header
+}
+
+/// whether the move checks the opponent, who has no reply which saves the king
+fn is_mate(board: &Board, mv: Move, color: Color) -> bool {
+    let mut board = board.clone();
+    if board.play(mv).is_err() {
+        return true;
+    }
+    board.is_in_check(color.opponent())
+        && board.moves(color.opponent()).into_iter().all(|reply| {
+            let mut board = board.clone();
+            board.play(reply).is_ok() && board.king_capture(color).is_some()
+        })
+}
@@ -0,0 +1,33 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// a small xorshift generator to vary the play of the computer, not meant for anything secret
+pub struct Random(u64);
+
+impl Random {
+    /// seeded from the system clock, so every run plays differently
+    pub fn from_time() -> Self {
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos() as u64).unwrap_or(0);
+        Random::with_seed(nanos)
+    }
+
+    pub fn with_seed(seed: u64) -> Self {
+        // the state must never be zero
+        Random((seed ^ 0x9e37_79b9_7f4a_7c15) | 1)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// a number from 0 up to, but not including, the bound
+    pub fn below(&mut self, bound: u64) -> u64 {
+        if bound == 0 {
+            0
+        } else {
+            self.next_u64() % bound
+        }
+    }
+}
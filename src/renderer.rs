@@ -0,0 +1,235 @@
+use crate::analysis::AnalysisView;
+use crate::board::{Board, Move};
+use crate::console::DisplaySettings;
+use crate::i18n::{tr, Message};
+use crate::pieces::{Color, Position};
+
+use std::io::{self, Write};
+
+/// draws the state of the game, the game loop only talks to this trait
+pub trait Renderer {
+    /// draws everything from scratch, used at the start and whenever the orientation, theme or size changes
+    fn init(&mut self, board: &Board, cursor_pos: &(u16, u16), move_set: &[Position], hint: Option<Move>, settings: &DisplaySettings) -> io::Result<()>;
+
+    /// redraws the board after a move of the cursor or a piece
+    fn board(&mut self, board: &Board, cursor_pos: &(u16, u16), move_set: &[Position], hint: Option<Move>, settings: &DisplaySettings) -> io::Result<()>;
+
+    /// shows the clocks of both players and a message like a draw offer, None clears the line
+    fn status(&mut self, clock: Option<&str>, message: Option<&str>, settings: &DisplaySettings) -> io::Result<()>;
+
+    /// shows the evaluation bar, the depth and the best lines of the analysis, None hides them
+    fn analysis(&mut self, analysis: Option<&AnalysisView>, settings: &DisplaySettings) -> io::Result<()>;
+
+    /// shows the result of the game, e.g. `Player WHITE has won!`
+    fn game_over(&mut self, result: &str) -> io::Result<()>;
+
+    /// maps a clicked terminal cell (column, row) onto the square drawn there
+    /// renderers without a fixed screen geometry return None
+    fn square_at(&self, _column: u16, _row: u16, _flipped: bool) -> Option<Position> {
+        None
+    }
+}
+
+/// writes the board as plain text to any writer, e.g. a pipe or a buffer
+///
+/// the cursor is marked with `[ ]`, the squares the selected piece can reach with `( )`,
+/// the squares of a suggested move with `< >`:
+/// ```text
+///    a  b  c  d  e  f  g  h
+/// 8  r  n  b  q  k  b  n  r  8
+/// 7  p  p  p  p  p  p  p  p  7
+/// ```
+pub struct TextRenderer<W: Write> {
+    out: W,
+    line_ending: &'static str,
+    // the clock is printed together with the board, printing it on every tick would flood the output
+    clock: Option<String>,
+    last_message: Option<String>,
+}
+
+impl<W: Write> TextRenderer<W> {
+    pub fn new(out: W) -> Self {
+        TextRenderer { out, line_ending: "\n", clock: None, last_message: None }
+    }
+
+    /// a terminal in raw mode needs `\r\n` to start a new line at its left edge
+    pub fn with_line_ending(mut self, line_ending: &'static str) -> Self {
+        self.line_ending = line_ending;
+        self
+    }
+
+    fn line(&mut self, text: &str) -> io::Result<()> {
+        write!(self.out, "{}{}", text, self.line_ending)
+    }
+}
+
+impl<W: Write> Renderer for TextRenderer<W> {
+    fn init(&mut self, board: &Board, cursor_pos: &(u16, u16), move_set: &[Position], hint: Option<Move>, settings: &DisplaySettings) -> io::Result<()> {
+        self.line(&format!("{}rusty-chess!", tr(Message::Welcome)))?;
+        self.board(board, cursor_pos, move_set, hint, settings)
+    }
+
+    fn board(&mut self, board: &Board, cursor_pos: &(u16, u16), move_set: &[Position], hint: Option<Move>, settings: &DisplaySettings) -> io::Result<()> {
+        let cursor = Position::new(cursor_pos.0 as i8, cursor_pos.1 as i8);
+        let order: Vec<i8> = if settings.flipped { (0..8).rev().collect() } else { (0..8).collect() };
+
+        let files: String = order.iter().map(|col| format!(" {} ", (b'a' + *col as u8) as char)).collect();
+        self.line(&format!("  {files}"))?;
+        for row in &order {
+            let mut line = format!("{} ", 8 - row);
+            for col in &order {
+                let position = Position::new(*col, *row);
+                let glyph = board[position].map(|p| settings.glyphs.glyph(&p)).unwrap_or('.');
+                let (left, right) = if position == cursor {
+                    ('[', ']')
+                } else if move_set.contains(&position) {
+                    ('(', ')')
+                } else if hint.is_some_and(|mv| mv.from == position || mv.to == position) {
+                    ('<', '>')
+                } else {
+                    (' ', ' ')
+                };
+                line.push(left);
+                line.push(glyph);
+                line.push(right);
+            }
+            line.push_str(&format!(" {}", 8 - row));
+            self.line(&line)?;
+        }
+        self.line(&format!("  {files}"))?;
+
+        if let Some((from, to)) = board.last_move {
+            let last_move = format!("{}-{}", from.to_square_name(), to.to_square_name());
+            self.line(&tr(Message::LastMove).replace("{}", &last_move))?;
+        }
+        for color in [Color::White, Color::Black] {
+            if board.is_in_check(color) {
+                self.line(&tr(Message::Check).replace("{}", &color.to_string()))?;
+            }
+        }
+        if let Some(clock) = self.clock.clone() {
+            self.line(&clock)?;
+        }
+        self.line("")?;
+        self.out.flush()
+    }
+
+    fn status(&mut self, clock: Option<&str>, message: Option<&str>, _settings: &DisplaySettings) -> io::Result<()> {
+        self.clock = clock.map(str::to_string);
+        let message = message.map(str::to_string);
+        if message != self.last_message {
+            if let Some(text) = &message {
+                self.line(text)?;
+            }
+            self.last_message = message;
+        }
+        self.out.flush()
+    }
+
+    /// prints every deeper iteration of the analysis, there is no bar to hide
+    fn analysis(&mut self, analysis: Option<&AnalysisView>, _settings: &DisplaySettings) -> io::Result<()> {
+        let Some(analysis) = analysis else {
+            return Ok(());
+        };
+        self.line(&analysis.heading())?;
+        for line in &analysis.lines {
+            self.line(line)?;
+        }
+        self.out.flush()
+    }
+
+    fn game_over(&mut self, result: &str) -> io::Result<()> {
+        self.line(result)?;
+        self.line(tr(Message::PressAnyKey))?;
+        self.out.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::glyphs::GlyphSet;
+
+    const FILES: &str = "   a  b  c  d  e  f  g  h ";
+    const FILES_FLIPPED: &str = "   h  g  f  e  d  c  b  a ";
+
+    fn square(name: &str) -> Position {
+        Position::from_square_name(name).unwrap()
+    }
+
+    /// draws the initial position with letters as glyphs, the cursor given as (column, row)
+    fn render(cursor: (u16, u16), move_set: &[Position], hint: Option<Move>, flipped: bool) -> String {
+        let mut board = Board::new();
+        board.initialize();
+        let settings = DisplaySettings { flipped, glyphs: GlyphSet::Letters, ..DisplaySettings::default() };
+        let mut out = Vec::new();
+        TextRenderer::new(&mut out).board(&board, &cursor, move_set, hint, &settings).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    fn snapshot(lines: &[&str]) -> String {
+        lines.iter().map(|line| format!("{line}\n")).collect::<String>() + "\n"
+    }
+
+    #[test]
+    fn initial_board() {
+        let mut board = Board::new();
+        board.initialize();
+        let settings = DisplaySettings { glyphs: GlyphSet::Letters, ..DisplaySettings::default() };
+        let mut out = Vec::new();
+        TextRenderer::new(&mut out).init(&board, &(4, 6), &[], None, &settings).unwrap();
+        let expected = snapshot(&[
+            "Welcome to rusty-chess!",
+            FILES,
+            "8  r  n  b  q  k  b  n  r  8",
+            "7  p  p  p  p  p  p  p  p  7",
+            "6  .  .  .  .  .  .  .  .  6",
+            "5  .  .  .  .  .  .  .  .  5",
+            "4  .  .  .  .  .  .  .  .  4",
+            "3  .  .  .  .  .  .  .  .  3",
+            "2  P  P  P  P [P] P  P  P  2",
+            "1  R  N  B  Q  K  B  N  R  1",
+            FILES,
+        ]);
+        assert_eq!(String::from_utf8(out).unwrap(), expected);
+    }
+
+    #[test]
+    fn flipped_board() {
+        let expected = snapshot(&[
+            FILES_FLIPPED,
+            "1  R  N  B  K  Q  B  N  R  1",
+            "2  P  P  P [P] P  P  P  P  2",
+            "3  .  .  .  .  .  .  .  .  3",
+            "4  .  .  .  .  .  .  .  .  4",
+            "5  .  .  .  .  .  .  .  .  5",
+            "6  .  .  .  .  .  .  .  .  6",
+            "7  p  p  p  p  p  p  p  p  7",
+            "8  r  n  b  k  q  b  n  r  8",
+            FILES_FLIPPED,
+        ]);
+        assert_eq!(render((4, 6), &[], None, true), expected);
+    }
+
+    #[test]
+    fn cursor() {
+        let board = render((6, 7), &[], None, false);
+        assert!(board.contains("1  R  N  B  Q  K  B [N] R  1\n"), "{board}");
+        assert_eq!(board.matches('[').count(), 1);
+    }
+
+    #[test]
+    fn move_set() {
+        let board = render((4, 6), &[square("e3"), square("e4")], None, false);
+        assert!(board.contains("4  .  .  .  . (.) .  .  .  4\n"), "{board}");
+        assert!(board.contains("3  .  .  .  . (.) .  .  .  3\n"), "{board}");
+        assert!(board.contains("2  P  P  P  P [P] P  P  P  2\n"), "{board}");
+    }
+
+    #[test]
+    fn hint() {
+        let board = render((4, 6), &[], Some(Move::new(square("g1"), square("f3"))), false);
+        assert!(board.contains("3  .  .  .  .  . <.> .  .  3\n"), "{board}");
+        assert!(board.contains("1  R  N  B  Q  K  B <N> R  1\n"), "{board}");
+    }
+}
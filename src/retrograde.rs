@@ -0,0 +1,423 @@
+use crate::board::{Board, Move};
+use crate::pieces::{Color, PieceVariant};
+
+use std::collections::HashMap;
+
+/// most pieces beside the king a table can hold, one more would need gigabytes
+pub const MAX_PIECES: usize = 2;
+// the order of the pieces in a table, e.g. `BN` and never `NB`
+const PIECE_ORDER: [PieceVariant; 5] =
+    [PieceVariant::Queen, PieceVariant::Rook, PieceVariant::Bishop, PieceVariant::Knight, PieceVariant::Pawn];
+const PROMOTIONS: [PieceVariant; 4] = [PieceVariant::Queen, PieceVariant::Rook, PieceVariant::Bishop, PieceVariant::Knight];
+// the moves left of a position of the defender which is never lost, e.g. because a piece can be captured for free
+const NEVER_LOST: u8 = u8::MAX;
+// the distances are stored in a byte, longer ones are not followed
+const MAX_PLIES: u8 = 250;
+
+const KING_STEPS: [(i8, i8); 8] = [(-1, -1), (0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1), (1, 1)];
+const KNIGHT_JUMPS: [(i8, i8); 8] = [(1, -2), (2, -1), (2, 1), (1, 2), (-1, 2), (-2, 1), (-2, -1), (-1, -2)];
+const ROOK_DIRECTIONS: [(i8, i8); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+const BISHOP_DIRECTIONS: [(i8, i8); 4] = [(1, -1), (1, 1), (-1, 1), (-1, -1)];
+
+/// the distance to the king capture of every position of a few pieces against a lone king
+///
+/// a position is a list of squares, 0 to 63 with row 0 at the 8th rank as in the board array: the king of the
+/// attacker, the king of the defender and the other pieces of the attacker in the order of the table. the pawns
+/// of the attacker move towards row 0, positions with a black attacker are mirrored
+struct Table {
+    pieces: Vec<PieceVariant>,
+    // plies until the attacker captures the king with best play on both sides, 0 if the attacker can't force it
+    attacker_to_move: Vec<u8>,
+    defender_to_move: Vec<u8>,
+}
+
+/// the tables of the endgames in use and of all endgames reached from them by captures and promotions
+#[derive(Default)]
+pub struct Tables {
+    tables: HashMap<String, Table>,
+}
+
+impl Tables {
+    /// computes the table of the pieces and the smaller tables it depends on, unless they are known already
+    pub fn solve(&mut self, pieces: &[PieceVariant]) {
+        let pieces = sorted(pieces);
+        assert!(pieces.len() <= MAX_PIECES, "tables hold at most {MAX_PIECES} pieces beside the king");
+        if self.tables.contains_key(&key(&pieces)) {
+            return;
+        }
+        for i in 0..pieces.len() {
+            let mut captured = pieces.clone();
+            captured.remove(i);
+            self.solve(&captured);
+            if pieces[i] == PieceVariant::Pawn {
+                for variant in PROMOTIONS {
+                    let mut promoted = pieces.clone();
+                    promoted[i] = variant;
+                    self.solve(&promoted);
+                }
+            }
+        }
+        let table = Table::solve(pieces, self);
+        self.tables.insert(key(&table.pieces), table);
+    }
+
+    /// plies until the attacker captures the lone king of the opponent with best play on both sides, 0 if the
+    /// attacker can't force it and None if the position is in none of the tables
+    pub fn distance(&self, board: &Board, attacker: Color, side_to_move: Color) -> Option<u8> {
+        let mut kings = [None; 2];
+        let mut pieces = vec![];
+        for p in board.board_array.iter().flatten().flatten() {
+            // the pawns of a black attacker move towards row 7, mirrored they move towards row 0 like white ones
+            let row = if attacker == Color::White { p.position.row } else { 7 - p.position.row };
+            let square = (row * 8 + p.position.col) as u8;
+            match (p.variant, p.color == attacker) {
+                (PieceVariant::King, true) => kings[0] = Some(square),
+                (PieceVariant::King, false) => kings[1] = Some(square),
+                (variant, true) => pieces.push((variant, square)),
+                (_, false) => return None,
+            }
+        }
+        let (Some(king), Some(defender)) = (kings[0], kings[1]) else {
+            return None;
+        };
+        self.lookup(king, defender, &mut pieces, side_to_move == attacker)
+    }
+
+    /// the move of the defender which delays the capture of its king the longest or avoids it for good,
+    /// None if the position is in none of the tables
+    pub fn best_defence(&self, board: &Board, defender: Color) -> Option<Move> {
+        let attacker = defender.opponent();
+        self.distance(board, attacker, defender)?;
+        // a move which is never lost is the best, otherwise the one which takes the attacker the longest
+        board.moves(defender).into_iter().max_by_key(|mv| {
+            let mut board = board.clone();
+            if board.play(*mv).is_err() {
+                return u16::MAX;
+            }
+            match self.distance(&board, attacker, attacker) {
+                Some(0) => u16::MAX - 1,
+                Some(plies) => plies as u16,
+                None => 0,
+            }
+        })
+    }
+
+    /// the distance of a position given by its pieces in any order
+    fn lookup(&self, king: u8, defender: u8, pieces: &mut [(PieceVariant, u8)], attacker_to_move: bool) -> Option<u8> {
+        pieces.sort_by_key(|(variant, _)| order(*variant));
+        let variants: Vec<PieceVariant> = pieces.iter().map(|(variant, _)| *variant).collect();
+        let table = self.tables.get(&key(&variants))?;
+        let mut squares = vec![king, defender];
+        squares.extend(pieces.iter().map(|(_, square)| *square));
+        let index = encode(&squares);
+        Some(if attacker_to_move { table.attacker_to_move[index] } else { table.defender_to_move[index] })
+    }
+}
+
+impl Table {
+    /// retrograde analysis: starting with the positions in which the king can be captured, every position won or
+    /// lost in n plies leads back to the positions won or lost in n + 1 plies, the smaller tables must be solved
+    fn solve(pieces: Vec<PieceVariant>, tables: &Tables) -> Table {
+        let size = 1 << (6 * (pieces.len() + 2));
+        let mut attacker_to_move = vec![0; size];
+        let mut defender_to_move = vec![0; size];
+        // the moves of the defender not known to lose yet
+        let mut remaining = vec![0; size];
+        // positions of the defender with a capture into a smaller table, by the distance of the reached position
+        let mut capture_losses: Vec<Vec<u32>> = vec![vec![]; MAX_PLIES as usize + 1];
+        // positions of the attacker with a promotion into a smaller table, by the distance after the promotion
+        let mut promotion_wins: Vec<Vec<u32>> = vec![vec![]; MAX_PLIES as usize + 1];
+
+        // the tables reached by capturing each of the pieces, the other pieces keep their order
+        let captures: Vec<Option<&Table>> = (0..pieces.len())
+            .map(|i| {
+                let mut rest = pieces.clone();
+                rest.remove(i);
+                tables.tables.get(&key(&rest))
+            })
+            .collect();
+        // the tables reached by promoting a pawn, with the order of the pieces there
+        let mut promotions = vec![];
+        for i in (0..pieces.len()).filter(|i| pieces[*i] == PieceVariant::Pawn) {
+            for variant in PROMOTIONS {
+                let mut promoted = pieces.clone();
+                promoted[i] = variant;
+                let mut order_there: Vec<usize> = (0..pieces.len()).collect();
+                order_there.sort_by_key(|k| order(promoted[*k]));
+                if let Some(table) = tables.tables.get(&key(&sorted(&promoted))) {
+                    promotions.push((i, table, order_there));
+                }
+            }
+        }
+
+        let mut won = vec![];
+        let mut squares = vec![0; pieces.len() + 2];
+        let mut other = [0; MAX_PIECES + 2];
+        for index in 0..size {
+            decode(index, &mut squares);
+            if !is_valid(&pieces, &squares) {
+                continue;
+            }
+            if attacks_king(&pieces, &squares) {
+                attacker_to_move[index] = 1;
+                won.push(index as u32);
+            } else {
+                for (i, table, order_there) in &promotions {
+                    let to = squares[i + 2].wrapping_sub(8);
+                    if squares[i + 2] / 8 != 1 || squares.contains(&to) {
+                        continue;
+                    }
+                    other[..2].copy_from_slice(&squares[..2]);
+                    for (k, piece) in order_there.iter().enumerate() {
+                        other[k + 2] = if piece == i { to } else { squares[piece + 2] };
+                    }
+                    let plies = table.defender_to_move[encode(&other[..squares.len()])];
+                    if plies > 0 && plies < MAX_PLIES {
+                        promotion_wins[plies as usize + 1].push(index as u32);
+                    }
+                }
+            }
+
+            // the defender can capture a king standing next to its own
+            if is_adjacent(squares[0], squares[1]) {
+                remaining[index] = NEVER_LOST;
+                continue;
+            }
+            let mut moves = 0;
+            for to in KING_STEPS.iter().filter_map(|step| shift(squares[1], *step)) {
+                let Some(captured) = squares[2..].iter().position(|square| *square == to) else {
+                    moves += 1;
+                    continue;
+                };
+                other[..squares.len()].copy_from_slice(&squares);
+                other[1] = to;
+                other.copy_within(captured + 3..squares.len(), captured + 2);
+                match captures[captured].map(|table| table.attacker_to_move[encode(&other[..squares.len() - 1])]) {
+                    Some(plies @ 1..) => {
+                        moves += 1;
+                        capture_losses[plies as usize].push(index as u32);
+                    }
+                    _ => {
+                        moves = NEVER_LOST;
+                        break;
+                    }
+                }
+            }
+            remaining[index] = moves;
+        }
+
+        let mut plies = 1;
+        while plies < MAX_PLIES {
+            // positions of the attacker won in `plies`, among them those won by a promotion
+            for &index in &promotion_wins[plies as usize] {
+                if attacker_to_move[index as usize] == 0 {
+                    attacker_to_move[index as usize] = plies;
+                    won.push(index);
+                }
+            }
+            // every move of the defender into them loses, a position of the defender is lost once all moves are
+            let mut lost = vec![];
+            let mut lose = |index: usize, lost: &mut Vec<u32>| {
+                if remaining[index] != NEVER_LOST && defender_to_move[index] == 0 {
+                    remaining[index] -= 1;
+                    if remaining[index] == 0 {
+                        defender_to_move[index] = plies + 1;
+                        lost.push(index as u32);
+                    }
+                }
+            };
+            for &index in &capture_losses[plies as usize] {
+                lose(index as usize, &mut lost);
+            }
+            for &index in &won {
+                decode(index as usize, &mut squares);
+                for from in KING_STEPS.iter().filter_map(|step| shift(squares[1], *step)) {
+                    if !squares.contains(&from) {
+                        lose(index as usize & !(63 << 6) | (from as usize) << 6, &mut lost);
+                    }
+                }
+            }
+
+            // every move of the attacker into a lost position of the defender wins
+            won = vec![];
+            for &index in &lost {
+                decode(index as usize, &mut squares);
+                for i in (0..squares.len()).filter(|i| *i != 1) {
+                    let variant = if i == 0 { PieceVariant::King } else { pieces[i - 2] };
+                    retreats(variant, squares[i], &squares, |from| {
+                        let previous = index as usize & !(63 << (6 * i)) | (from as usize) << (6 * i);
+                        if attacker_to_move[previous] == 0 {
+                            attacker_to_move[previous] = plies + 2;
+                            won.push(previous as u32);
+                        }
+                    });
+                }
+            }
+            plies += 2;
+        }
+        Table { pieces, attacker_to_move, defender_to_move }
+    }
+}
+
+fn order(variant: PieceVariant) -> usize {
+    PIECE_ORDER.iter().position(|v| *v == variant).unwrap_or(PIECE_ORDER.len())
+}
+
+fn sorted(pieces: &[PieceVariant]) -> Vec<PieceVariant> {
+    let mut pieces = pieces.to_vec();
+    pieces.sort_by_key(|variant| order(*variant));
+    pieces
+}
+
+/// the name of a table by the pieces beside the king, e.g. `BN`
+fn key(pieces: &[PieceVariant]) -> String {
+    pieces.iter().map(|variant| variant.letter()).collect()
+}
+
+fn encode(squares: &[u8]) -> usize {
+    squares.iter().rev().fold(0, |index, square| (index << 6) | *square as usize)
+}
+
+fn decode(index: usize, squares: &mut [u8]) {
+    for (i, square) in squares.iter_mut().enumerate() {
+        *square = (index >> (6 * i) & 63) as u8;
+    }
+}
+
+/// the square in the direction of (column, row), None beyond the edge of the board
+fn shift(square: u8, (dc, dr): (i8, i8)) -> Option<u8> {
+    let (col, row) = ((square % 8) as i8 + dc, (square / 8) as i8 + dr);
+    ((0..8).contains(&col) && (0..8).contains(&row)).then_some((row * 8 + col) as u8)
+}
+
+fn is_adjacent(a: u8, b: u8) -> bool {
+    a != b && (a % 8).abs_diff(b % 8) <= 1 && (a / 8).abs_diff(b / 8) <= 1
+}
+
+/// all squares different and no pawn on the first or last rank
+fn is_valid(pieces: &[PieceVariant], squares: &[u8]) -> bool {
+    let distinct = (1..squares.len()).all(|i| !squares[..i].contains(&squares[i]));
+    distinct && pieces.iter().zip(&squares[2..]).all(|(variant, square)| *variant != PieceVariant::Pawn || (1..7).contains(&(square / 8)))
+}
+
+/// whether a piece of the attacker can capture the king of the defender
+fn attacks_king(pieces: &[PieceVariant], squares: &[u8]) -> bool {
+    let target = squares[1];
+    is_adjacent(squares[0], target)
+        || pieces.iter().zip(&squares[2..]).any(|(variant, from)| match variant {
+            PieceVariant::Pawn => [(-1, -1), (1, -1)].iter().any(|step| shift(*from, *step) == Some(target)),
+            PieceVariant::Knight => KNIGHT_JUMPS.iter().any(|step| shift(*from, *step) == Some(target)),
+            PieceVariant::Bishop => slides_to(*from, target, &BISHOP_DIRECTIONS, squares),
+            PieceVariant::Rook => slides_to(*from, target, &ROOK_DIRECTIONS, squares),
+            PieceVariant::Queen => {
+                slides_to(*from, target, &BISHOP_DIRECTIONS, squares) || slides_to(*from, target, &ROOK_DIRECTIONS, squares)
+            }
+            PieceVariant::King => false,
+        })
+}
+
+fn slides_to(from: u8, target: u8, directions: &[(i8, i8)], squares: &[u8]) -> bool {
+    directions.iter().any(|direction| {
+        let mut square = from;
+        while let Some(next) = shift(square, *direction) {
+            if next == target {
+                return true;
+            }
+            if squares.contains(&next) {
+                return false;
+            }
+            square = next;
+        }
+        false
+    })
+}
+
+/// calls back with every empty square a piece of the attacker can have come from, captures are impossible
+/// with a lone king
+fn retreats(variant: PieceVariant, square: u8, squares: &[u8], mut retreat: impl FnMut(u8)) {
+    let empty = |square: &u8| !squares.contains(square);
+    let mut steps = |steps: &[(i8, i8)]| steps.iter().filter_map(|step| shift(square, *step)).filter(empty).for_each(&mut retreat);
+    let slides = |directions: &[(i8, i8)], retreat: &mut dyn FnMut(u8)| {
+        for direction in directions {
+            let mut current = square;
+            while let Some(next) = shift(current, *direction).filter(empty) {
+                retreat(next);
+                current = next;
+            }
+        }
+    };
+    match variant {
+        PieceVariant::King => steps(&KING_STEPS),
+        PieceVariant::Knight => steps(&KNIGHT_JUMPS),
+        PieceVariant::Bishop => slides(&BISHOP_DIRECTIONS, &mut retreat),
+        PieceVariant::Rook => slides(&ROOK_DIRECTIONS, &mut retreat),
+        PieceVariant::Queen => {
+            slides(&BISHOP_DIRECTIONS, &mut retreat);
+            slides(&ROOK_DIRECTIONS, &mut retreat);
+        }
+        // one row back, or two from the starting row, promotions are found from the table with the pawn
+        PieceVariant::Pawn => {
+            if let Some(one) = shift(square, (0, 1)).filter(|one| empty(one) && one / 8 <= 6) {
+                retreat(one);
+                if square / 8 == 4 {
+                    shift(one, (0, 1)).filter(empty).into_iter().for_each(retreat);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Game;
+
+    fn solved(pieces: &[PieceVariant]) -> Tables {
+        let mut tables = Tables::default();
+        tables.solve(pieces);
+        tables
+    }
+
+    /// the longest distance with the attacker to move in the table of the pieces
+    fn longest(tables: &Tables, pieces: &[PieceVariant]) -> u8 {
+        tables.tables[&key(pieces)].attacker_to_move.iter().copied().max().unwrap()
+    }
+
+    fn distance(tables: &Tables, fen: &str, attacker: Color) -> Option<u8> {
+        let game = Game::from_fen(fen, None).unwrap();
+        tables.distance(&game.board, attacker, game.side_to_move)
+    }
+
+    #[test]
+    fn longest_distances() {
+        let tables = solved(&[PieceVariant::Queen]);
+        assert_eq!(longest(&tables, &[PieceVariant::Queen]), 17);
+        let tables = solved(&[PieceVariant::Rook]);
+        assert_eq!(longest(&tables, &[PieceVariant::Rook]), 33);
+        let tables = solved(&[PieceVariant::Bishop, PieceVariant::Knight]);
+        assert_eq!(longest(&tables, &[PieceVariant::Bishop, PieceVariant::Knight]), 39);
+    }
+
+    #[test]
+    fn mate_in_one_captures_the_king_on_the_third_ply() {
+        let tables = solved(&[PieceVariant::Rook]);
+        assert_eq!(distance(&tables, "k7/8/1K6/8/8/8/8/7R w", Color::White), Some(3));
+        // the same position with colors swapped and ranks mirrored
+        assert_eq!(distance(&tables, "7r/8/8/8/8/1k6/8/K7 b", Color::Black), Some(3));
+    }
+
+    #[test]
+    fn pawn_promotes_with_mate() {
+        let tables = solved(&[PieceVariant::Pawn]);
+        assert_eq!(distance(&tables, "k7/2P5/1K6/8/8/8/8/8 w", Color::White), Some(3));
+    }
+
+    #[test]
+    fn defender_captures_the_pawn() {
+        let tables = solved(&[PieceVariant::Pawn]);
+        assert_eq!(distance(&tables, "8/8/8/8/8/k7/1P6/7K b", Color::White), Some(0));
+        let game = Game::from_fen("8/8/8/8/8/k7/1P6/7K b", None).unwrap();
+        let defence = tables.best_defence(&game.board, Color::Black).unwrap();
+        assert_eq!(defence.to_uci(), "a3b2");
+    }
+}
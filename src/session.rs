@@ -0,0 +1,59 @@
+use crossterm::{
+    cursor,
+    event::{DisableMouseCapture, EnableMouseCapture},
+    execute,
+    style::ResetColor,
+    terminal::{self, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use std::io;
+use std::panic;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// whether the alternate screen has been entered, the panic hook has no access to the session
+static ALTERNATE_SCREEN: AtomicBool = AtomicBool::new(false);
+
+/// puts the terminal into the state the game needs: alternate screen, raw mode, hidden cursor and mouse capture
+/// everything is restored when the session is dropped, on a panic the panic hook takes care of it
+pub struct TerminalSession;
+
+impl TerminalSession {
+    pub fn start() -> io::Result<TerminalSession> {
+        install_panic_hook();
+        terminal::enable_raw_mode()?;
+        // from here on the session restores the terminal, also if entering the alternate screen fails
+        let session = TerminalSession;
+        ALTERNATE_SCREEN.store(true, Ordering::SeqCst);
+        execute!(io::stdout(), EnterAlternateScreen, cursor::Hide, EnableMouseCapture)?;
+        Ok(session)
+    }
+
+    /// only enables raw mode to read single key presses, the output stays untouched for the text renderer
+    pub fn start_raw() -> io::Result<TerminalSession> {
+        install_panic_hook();
+        terminal::enable_raw_mode()?;
+        Ok(TerminalSession)
+    }
+}
+
+impl Drop for TerminalSession {
+    fn drop(&mut self) {
+        restore_terminal();
+    }
+}
+
+/// brings the terminal back into the state it had before the game started
+fn restore_terminal() {
+    if ALTERNATE_SCREEN.swap(false, Ordering::SeqCst) {
+        let _ = execute!(io::stdout(), ResetColor, DisableMouseCapture, cursor::Show, LeaveAlternateScreen);
+    }
+    let _ = terminal::disable_raw_mode();
+}
+
+/// restores the terminal before the panic message is printed, otherwise it would be lost on the alternate screen
+fn install_panic_hook() {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        restore_terminal();
+        default_hook(info);
+    }));
+}
@@ -0,0 +1,67 @@
+use crate::board::Move;
+use crate::engine::MATE_THRESHOLD;
+use crate::random::Random;
+
+pub const MAX_SKILL: u8 = 20;
+/// the range of target ratings, mapped linearly onto the skill levels
+pub const MIN_ELO: u32 = 600;
+pub const MAX_ELO: u32 = 2400;
+
+// the largest random amount added to the score of a move at skill level 1, in centipawns
+const MAX_NOISE: i32 = 400;
+
+/// how well the built-in engine plays, from 1 for beginners up to 20 for full strength
+///
+/// weaker levels search less deep, stop deepening after fewer nodes and add random noise to the scores
+/// of the moves, so they now and then prefer a worse one
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Skill(u8);
+
+impl Default for Skill {
+    fn default() -> Self {
+        Skill(MAX_SKILL)
+    }
+}
+
+impl Skill {
+    pub fn new(level: u8) -> Self {
+        Skill(level.clamp(1, MAX_SKILL))
+    }
+
+    /// the level for a target rating, a rough guess which has not been measured against rated players
+    pub fn from_elo(elo: u32) -> Self {
+        let elo = elo.clamp(MIN_ELO, MAX_ELO);
+        Skill::new(1 + ((elo - MIN_ELO) * (MAX_SKILL as u32 - 1) / (MAX_ELO - MIN_ELO)) as u8)
+    }
+
+    pub fn level(self) -> u8 {
+        self.0
+    }
+
+    pub fn is_full(self) -> bool {
+        self.0 == MAX_SKILL
+    }
+
+    /// the deepest search of this level, none for full strength
+    pub fn max_depth(self) -> Option<u8> {
+        (!self.is_full()).then_some(1 + (self.0 - 1) / 4)
+    }
+
+    /// no further iteration is started after this many nodes
+    pub fn node_limit(self) -> Option<u64> {
+        (!self.is_full()).then(|| 200 << (self.0 / 2))
+    }
+
+    /// picks one of the moves by their scores with random noise, a captured king is never missed
+    pub fn choose(self, moves: &[(Move, i32)], random: &mut Random) -> Option<Move> {
+        let noise = MAX_NOISE * (MAX_SKILL - self.0) as i32 / (MAX_SKILL - 1) as i32;
+        moves
+            .iter()
+            .map(|(mv, score)| {
+                let blur = if score.abs() > MATE_THRESHOLD { 0 } else { random.below(noise as u64 + 1) as i32 };
+                (*mv, score + blur)
+            })
+            .max_by_key(|(_, score)| *score)
+            .map(|(mv, _)| mv)
+    }
+}
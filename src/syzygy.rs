@@ -0,0 +1,1116 @@
+use crate::board::{Board, Move};
+use crate::pieces::{Color, PieceVariant, Position};
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+
+// the first four bytes of every WDL and DTZ file
+const WDL_MAGIC: [u8; 4] = [0x71, 0xe8, 0x23, 0x5d];
+const DTZ_MAGIC: [u8; 4] = [0xd7, 0x66, 0x0c, 0xa5];
+/// the most pieces, kings included, the format has tables for
+pub const MAX_PIECES: usize = 7;
+
+// flags of the first byte of a file: both sides to move are stored, there are pawns
+const SPLIT: u8 = 1;
+const HAS_PAWNS: u8 = 2;
+// flags of a table: the side to move of a DTZ table, how the values are mapped and whether they count plies or moves
+const STM: u8 = 1;
+const MAPPED: u8 = 2;
+const WIN_PLIES: u8 = 4;
+const LOSS_PLIES: u8 = 8;
+const WIDE: u8 = 16;
+const SINGLE_VALUE: u8 = 128;
+
+// results of the tables from the perspective of the side to move, a cursed win is only won if the fifty-move rule
+// doesn't apply, a blessed loss is only lost then
+const LOSS: i32 = -2;
+const BLESSED_LOSS: i32 = -1;
+const DRAW: i32 = 0;
+const CURSED_WIN: i32 = 1;
+const WIN: i32 = 2;
+
+/// the result of a position with perfect play from the perspective of the side to move
+///
+/// the tables follow the rules of chess with the fifty-move rule, which this game doesn't know,
+/// so a cursed win is as good as a win here and a blessed loss as bad as a loss
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Wdl {
+    Loss,
+    BlessedLoss,
+    Draw,
+    CursedWin,
+    Win,
+}
+
+impl Wdl {
+    fn from_value(value: i32) -> Wdl {
+        match value {
+            LOSS => Wdl::Loss,
+            BLESSED_LOSS => Wdl::BlessedLoss,
+            CURSED_WIN => Wdl::CursedWin,
+            WIN => Wdl::Win,
+            _ => Wdl::Draw,
+        }
+    }
+}
+
+/// the Syzygy endgame tablebases of a directory, the tables are read into memory when they are needed first
+///
+/// the results count for this game as well where a side wins: stalemate only adds wins to it, and the captures
+/// en passant the tables expect in lines with a double pawn step are rarely what decides the game
+pub struct Tablebases {
+    dir: PathBuf,
+    // the material of the tables found, e.g. `KRPvKR`
+    wdl: HashSet<String>,
+    dtz: HashSet<String>,
+    max_pieces: usize,
+    tables: Mutex<Loaded>,
+}
+
+// the tables read so far by material and whether they are DTZ tables, None for broken files
+type Loaded = HashMap<(String, bool), Option<Arc<Table>>>;
+
+impl Tablebases {
+    /// looks for `.rtbw` and `.rtbz` files in the directory, they are checked when they are loaded
+    pub fn open(dir: &Path) -> io::Result<Tablebases> {
+        let entries = fs::read_dir(dir)
+            .map_err(|e| io::Error::new(e.kind(), format!("could not read the tablebase directory {}: {e}", dir.display())))?;
+        let (mut wdl, mut dtz) = (HashSet::new(), HashSet::new());
+        for path in entries.flatten().map(|entry| entry.path()) {
+            let (Some(name), Some(extension)) = (path.file_stem().and_then(|name| name.to_str()), path.extension()) else {
+                continue;
+            };
+            if Material::parse(name).is_none() {
+                continue;
+            }
+            match extension.to_str() {
+                Some("rtbw") => wdl.insert(name.to_string()),
+                Some("rtbz") => dtz.insert(name.to_string()),
+                _ => false,
+            };
+        }
+        let max_pieces = wdl.iter().map(|name| name.len() - 1).max().unwrap_or(0);
+        Ok(Tablebases { dir: dir.to_path_buf(), wdl, dtz, max_pieces, tables: Mutex::new(HashMap::new()) })
+    }
+
+    /// the number of WDL and DTZ tables found
+    pub fn count(&self) -> usize {
+        self.wdl.len() + self.dtz.len()
+    }
+
+    /// the most pieces, kings included, of any WDL table found
+    pub fn max_pieces(&self) -> usize {
+        self.max_pieces
+    }
+
+    /// the result of the position, None if there is no table for it or the opponent's king can be captured
+    pub fn probe_wdl(&self, board: &Board, color: Color) -> Option<Wdl> {
+        if !self.knows(board, color) {
+            return None;
+        }
+        let (wdl, _) = self.search(&mut board.clone(), color, false)?;
+        Some(Wdl::from_value(wdl))
+    }
+
+    /// the plies until the next capture or pawn move with perfect play, positive if the side to move wins,
+    /// negative if it loses and 0 for a draw, None if there are no tables for the position
+    ///
+    /// a position which is mate counts as -1
+    pub fn probe_dtz(&self, board: &Board, color: Color) -> Option<i32> {
+        if !self.knows(board, color) {
+            return None;
+        }
+        self.dtz(&mut board.clone(), color)
+    }
+
+    /// all legal moves with the plies until the next capture or pawn move they lead to, including the move itself,
+    /// ordered from the best to the worst: the wins closest to that move, the draws, the losses furthest from it
+    pub fn rank(&self, board: &Board, color: Color) -> Option<Vec<(Move, i32)>> {
+        if !self.knows(board, color) {
+            return None;
+        }
+        let mut board = board.clone();
+        let opponent = color.opponent();
+        let mut ranked = vec![];
+        for mv in legal_moves(&mut board, color) {
+            let zeroing = is_zeroing(&board, mv);
+            let captured = board.make_move(mv);
+            let dtz = if zeroing {
+                self.search(&mut board, opponent, false).map(|(wdl, _)| before_zeroing(-wdl))
+            } else {
+                self.dtz(&mut board, opponent).map(|dtz| -dtz + (-dtz).signum())
+            };
+            let mate = dtz == Some(2) && board.is_in_check(opponent) && legal_moves(&mut board, opponent).is_empty();
+            board.unmake_move(mv, captured);
+            ranked.push((mv, if mate { 1 } else { dtz? }));
+        }
+        ranked.sort_by_key(|(_, dtz)| match dtz.signum() {
+            1 => (0, *dtz),
+            0 => (1, 0),
+            _ => (2, *dtz),
+        });
+        Some(ranked)
+    }
+
+    /// whether the position is one the tables can be asked about: few enough pieces, one king of each color and
+    /// no king which could be captured right away
+    fn knows(&self, board: &Board, color: Color) -> bool {
+        let pieces: Vec<_> = board.board_array.iter().flatten().flatten().collect();
+        let kings = |color: Color| pieces.iter().filter(|p| p.variant == PieceVariant::King && p.color == color).count();
+        pieces.len() <= self.max_pieces.min(MAX_PIECES)
+            && kings(Color::White) == 1
+            && kings(Color::Black) == 1
+            && !board.is_in_check(color.opponent())
+    }
+
+    /// the table of the material on the board and whether black has the material of the first side of its name
+    fn table(&self, board: &Board, dtz: bool) -> Option<(Arc<Table>, bool)> {
+        let (white, black) = (material(board, Color::White), material(board, Color::Black));
+        let names = if dtz { &self.dtz } else { &self.wdl };
+        let (name, black_stronger) = [(format!("{white}v{black}"), false), (format!("{black}v{white}"), true)]
+            .into_iter()
+            .find(|(name, _)| names.contains(name))?;
+        let mut tables = self.tables.lock().expect("no thread panics while loading a table");
+        let table = tables.entry((name.clone(), dtz)).or_insert_with(|| Table::load(&self.dir, &name, dtz).map(Arc::new));
+        Some((table.clone()?, black_stronger))
+    }
+
+    /// the value stored for the position, which may be wrong where the best move captures
+    fn probe_table(&self, board: &Board, color: Color, dtz: bool, wdl: i32) -> Result<i32, Probe> {
+        // the tables start with three pieces, two kings are a draw
+        if board.board_array.iter().flatten().flatten().count() == 2 {
+            return Ok(DRAW);
+        }
+        let (table, black_stronger) = self.table(board, dtz).ok_or(Probe::Fail)?;
+        table.probe(board, color, black_stronger, wdl)
+    }
+
+    /// the result of the position by trying the captures, and with `zeroing_moves` the pawn moves, before the table
+    /// is read, together with whether one of these moves is the best
+    ///
+    /// the tables store any value for positions in which such a move wins, so they can be compressed better
+    fn search(&self, board: &mut Board, color: Color, zeroing_moves: bool) -> Option<(i32, bool)> {
+        let moves = legal_moves(board, color);
+        let mut best = LOSS;
+        let mut searched = 0;
+        for &mv in &moves {
+            let tried = if zeroing_moves { is_zeroing(board, mv) } else { board[mv.to].is_some() };
+            if !tried {
+                continue;
+            }
+            searched += 1;
+            let captured = board.make_move(mv);
+            let value = self.search(board, color.opponent(), false).map(|(wdl, _)| -wdl);
+            board.unmake_move(mv, captured);
+            let value = value?;
+            if value > best {
+                best = value;
+                if value >= WIN {
+                    return Some((value, true));
+                }
+            }
+        }
+        // without any other move the table isn't needed, it may not even be right
+        let all_searched = searched > 0 && searched == moves.len();
+        let value = if all_searched { best } else { self.probe_table(board, color, false, DRAW).ok()? };
+        if best >= value {
+            return Some((best, best > DRAW || all_searched));
+        }
+        Some((value, false))
+    }
+
+    fn dtz(&self, board: &mut Board, color: Color) -> Option<i32> {
+        let (wdl, zeroing_best) = self.search(board, color, true)?;
+        // DTZ tables don't store draws
+        if wdl == DRAW {
+            return Some(0);
+        }
+        if zeroing_best {
+            return Some(before_zeroing(wdl));
+        }
+        match self.probe_table(board, color, true, wdl) {
+            Ok(dtz) => {
+                let cursed = if wdl == CURSED_WIN || wdl == BLESSED_LOSS { 100 } else { 0 };
+                Some((dtz + cursed) * wdl.signum())
+            }
+            Err(Probe::Fail) => None,
+            // the table stores the other side to move, the best move is found by looking one ply ahead
+            Err(Probe::ChangeSideToMove) => {
+                let opponent = color.opponent();
+                let mut min = i32::MAX;
+                for mv in legal_moves(board, color) {
+                    let zeroing = is_zeroing(board, mv);
+                    let captured = board.make_move(mv);
+                    let dtz = if zeroing {
+                        self.search(board, opponent, false).map(|(wdl, _)| -before_zeroing(wdl))
+                    } else {
+                        self.dtz(board, opponent).map(|dtz| -dtz)
+                    };
+                    let mate = dtz == Some(1) && board.is_in_check(opponent) && legal_moves(board, opponent).is_empty();
+                    board.unmake_move(mv, captured);
+                    let mut dtz = dtz?;
+                    if mate {
+                        min = 1;
+                    }
+                    if !zeroing {
+                        dtz += dtz.signum();
+                    }
+                    if dtz < min && dtz.signum() == wdl.signum() {
+                        min = dtz;
+                    }
+                }
+                Some(if min == i32::MAX { -1 } else { min })
+            }
+        }
+    }
+}
+
+/// why a table has no value for a position
+#[derive(Copy, Clone, PartialEq, Debug)]
+enum Probe {
+    Fail,
+    // DTZ tables store one side to move only
+    ChangeSideToMove,
+}
+
+/// the plies until the next capture or pawn move for a position whose best move is one
+fn before_zeroing(wdl: i32) -> i32 {
+    match wdl {
+        WIN => 1,
+        CURSED_WIN => 101,
+        BLESSED_LOSS => -101,
+        LOSS => -1,
+        _ => 0,
+    }
+}
+
+/// whether a move captures or moves a pawn, which resets the fifty-move rule
+fn is_zeroing(board: &Board, mv: Move) -> bool {
+    board[mv.to].is_some() || board[mv.from].is_some_and(|p| p.variant == PieceVariant::Pawn)
+}
+
+/// the moves of chess, which never leave the own king attacked
+fn legal_moves(board: &mut Board, color: Color) -> Vec<Move> {
+    board
+        .moves(color)
+        .into_iter()
+        .filter(|mv| {
+            let captured = board.make_move(*mv);
+            let legal = !board.is_in_check(color);
+            board.unmake_move(*mv, captured);
+            legal
+        })
+        .collect()
+}
+
+/// the pieces of a color as in the names of the tables, e.g. `KRP`
+fn material(board: &Board, color: Color) -> String {
+    let pieces: Vec<_> = board.board_array.iter().flatten().flatten().filter(|p| p.color == color).collect();
+    ORDER.iter().flat_map(|variant| pieces.iter().filter(move |p| p.variant == *variant).map(|p| p.variant.letter())).collect()
+}
+
+// the order of the pieces in the names of the tables
+const ORDER: [PieceVariant; 6] =
+    [PieceVariant::King, PieceVariant::Queen, PieceVariant::Rook, PieceVariant::Bishop, PieceVariant::Knight, PieceVariant::Pawn];
+
+/// the pieces of a table as its name tells them, the first side is the one the tables call white
+struct Material {
+    // the number of pieces of each side by the order of the names
+    counts: [[usize; 6]; 2],
+}
+
+impl Material {
+    fn parse(name: &str) -> Option<Material> {
+        let (first, second) = name.split_once('v')?;
+        let mut counts = [[0; 6]; 2];
+        for (side, pieces) in [first, second].into_iter().enumerate() {
+            for letter in pieces.chars() {
+                let variant = PieceVariant::from_letter(letter).filter(|_| letter.is_ascii_uppercase())?;
+                counts[side][ORDER.iter().position(|v| *v == variant)?] += 1;
+            }
+            if counts[side][0] != 1 {
+                return None;
+            }
+        }
+        let pieces: usize = counts.iter().flatten().sum();
+        (3..=MAX_PIECES).contains(&pieces).then_some(Material { counts })
+    }
+
+    fn pieces(&self) -> usize {
+        self.counts.iter().flatten().sum()
+    }
+
+    fn pawns(&self, side: usize) -> usize {
+        self.counts[side][5]
+    }
+}
+
+/// the code of a piece in the tables, black pieces have the bit 8 set
+fn piece_code(variant: PieceVariant, color: Color) -> u8 {
+    let code = match variant {
+        PieceVariant::Pawn => 1,
+        PieceVariant::Knight => 2,
+        PieceVariant::Bishop => 3,
+        PieceVariant::Rook => 4,
+        PieceVariant::Queen => 5,
+        PieceVariant::King => 6,
+    };
+    if color == Color::Black {
+        code | 8
+    } else {
+        code
+    }
+}
+
+// squares are numbered from a1 = 0 to h8 = 63 in the tables
+fn square(position: Position) -> usize {
+    ((7 - position.row) * 8 + position.col) as usize
+}
+
+fn file_of(square: usize) -> usize {
+    square & 7
+}
+
+fn rank_of(square: usize) -> usize {
+    square >> 3
+}
+
+/// how far a square is above the a1-h8 diagonal, negative below it
+fn off_diagonal(square: usize) -> i32 {
+    rank_of(square) as i32 - file_of(square) as i32
+}
+
+/// the tables mapping the squares of the pieces to the index of a position
+struct Encoding {
+    // the squares below the a1-h8 diagonal
+    map_b1h1h7: [u64; 64],
+    // the squares of the a1-d1-d4 triangle, those on the diagonal last
+    map_a1d1d4: [usize; 64],
+    // the 462 placements of two kings with the first one in the triangle
+    map_kk: [[u64; 64]; 10],
+    // binomial[k][n] ways to choose k of n squares
+    binomial: [[u64; 64]; MAX_PIECES - 1],
+    // the squares a2 to h7, the higher the nearer the leading pawn is to the edge and to the 2nd rank
+    map_pawns: [usize; 64],
+    lead_pawn_idx: [[u64; 64]; MAX_PIECES - 1],
+    lead_pawns_size: [[u64; 4]; MAX_PIECES - 1],
+}
+
+static ENCODING: OnceLock<Encoding> = OnceLock::new();
+
+fn encoding() -> &'static Encoding {
+    ENCODING.get_or_init(|| {
+        let mut map_b1h1h7 = [0; 64];
+        for (code, s) in (0..64).filter(|s| off_diagonal(*s) < 0).enumerate() {
+            map_b1h1h7[s] = code as u64;
+        }
+
+        let mut map_a1d1d4 = [0; 64];
+        let triangle = (0..=27).filter(|s| off_diagonal(*s) < 0 && file_of(*s) <= 3);
+        let diagonal = (0..=27).filter(|s| off_diagonal(*s) == 0 && file_of(*s) <= 3);
+        for (code, s) in triangle.chain(diagonal).enumerate() {
+            map_a1d1d4[s] = code;
+        }
+
+        let mut map_kk = [[0; 64]; 10];
+        let mut both_on_diagonal = vec![];
+        let mut code = 0;
+        for (idx, row) in map_kk.iter_mut().enumerate() {
+            // b1 is the only square of the first king mapped to 0, squares outside the triangle are 0 as well
+            for s1 in (0..=27).filter(|s1| map_a1d1d4[*s1] == idx && (idx > 0 || *s1 == 1)) {
+                for (s2, entry) in row.iter_mut().enumerate() {
+                    let adjacent = file_of(s1).abs_diff(file_of(s2)) <= 1 && rank_of(s1).abs_diff(rank_of(s2)) <= 1;
+                    if adjacent || off_diagonal(s1) == 0 && off_diagonal(s2) > 0 {
+                        continue;
+                    }
+                    if off_diagonal(s1) == 0 && off_diagonal(s2) == 0 {
+                        both_on_diagonal.push((idx, s2));
+                    } else {
+                        *entry = code;
+                        code += 1;
+                    }
+                }
+            }
+        }
+        for (idx, s2) in both_on_diagonal {
+            map_kk[idx][s2] = code;
+            code += 1;
+        }
+
+        let mut binomial = [[0; 64]; MAX_PIECES - 1];
+        binomial[0][0] = 1;
+        for n in 1..64 {
+            for k in 0..(MAX_PIECES - 1).min(n + 1) {
+                binomial[k][n] = if k > 0 { binomial[k - 1][n - 1] } else { 0 } + if k < n { binomial[k][n - 1] } else { 0 };
+            }
+        }
+
+        let mut map_pawns = [0; 64];
+        let mut lead_pawn_idx = [[0; 64]; MAX_PIECES - 1];
+        let mut lead_pawns_size = [[0; 4]; MAX_PIECES - 1];
+        let mut available: usize = 47;
+        for lead_pawns in 1..MAX_PIECES - 1 {
+            for (file, size) in lead_pawns_size[lead_pawns].iter_mut().enumerate() {
+                let mut idx = 0;
+                for rank in 1..7 {
+                    let s = rank * 8 + file;
+                    if lead_pawns == 1 {
+                        map_pawns[s] = available;
+                        map_pawns[s ^ 7] = available - 1;
+                        available = available.saturating_sub(2);
+                    }
+                    lead_pawn_idx[lead_pawns][s] = idx;
+                    idx += binomial[lead_pawns - 1][map_pawns[s]];
+                }
+                *size = idx;
+            }
+        }
+        Encoding { map_b1h1h7, map_a1d1d4, map_kk, binomial, map_pawns, lead_pawn_idx, lead_pawns_size }
+    })
+}
+
+/// the values of one side to move and one file of the leading pawn, compressed with Huffman codes of symbols which
+/// stand for pairs of other symbols
+#[derive(Default)]
+struct PairsData {
+    flags: u8,
+    // the order of the pieces in the index
+    pieces: [u8; MAX_PIECES],
+    // the groups of pieces encoded together, ended by 0, and the factor of each group in the index
+    group_len: [usize; MAX_PIECES + 1],
+    group_idx: [u64; MAX_PIECES + 1],
+    block_size: usize,
+    // every this many values the sparse index points into the blocks
+    span: u64,
+    num_blocks: usize,
+    // the value of a table with a single one
+    min_sym_len: u8,
+    // offsets in the file
+    lowest_sym: usize,
+    btree: usize,
+    sparse_index: usize,
+    sparse_index_size: usize,
+    block_lengths: usize,
+    block_lengths_size: usize,
+    data: usize,
+    // the lowest code of each length, left-aligned to 64 bits
+    base64: Vec<u64>,
+    // the number of values a symbol stands for, less one
+    symlen: Vec<u8>,
+    // where the values of each result start in the map of a DTZ table, plus one
+    map_idx: [usize; 4],
+}
+
+/// one WDL or DTZ file
+struct Table {
+    data: Vec<u8>,
+    dtz: bool,
+    // both sides have the same pieces
+    symmetric: bool,
+    has_pawns: bool,
+    has_unique_pieces: bool,
+    pawn_count: [usize; 2],
+    // by side to move, a DTZ table has one, and by the file of the leading pawn
+    pairs: Vec<Vec<PairsData>>,
+    // the offset of the map of a DTZ table
+    map: usize,
+}
+
+impl Table {
+    fn load(dir: &Path, name: &str, dtz: bool) -> Option<Table> {
+        let extension = if dtz { "rtbz" } else { "rtbw" };
+        let data = fs::read(dir.join(format!("{name}.{extension}"))).ok()?;
+        Table::parse(name, data, dtz)
+    }
+
+    /// reads the layout of the file, None if it doesn't fit the material of the name
+    fn parse(name: &str, data: Vec<u8>, dtz: bool) -> Option<Table> {
+        let material = Material::parse(name)?;
+        if data.get(..4)? != if dtz { DTZ_MAGIC } else { WDL_MAGIC } {
+            return None;
+        }
+        let has_pawns = material.pawns(0) + material.pawns(1) > 0;
+        // the side with the fewer pawns leads, as long as it has some
+        let white_leads = material.pawns(1) == 0 || material.pawns(0) > 0 && material.pawns(1) >= material.pawns(0);
+        let pawn_count = if white_leads { [material.pawns(0), material.pawns(1)] } else { [material.pawns(1), material.pawns(0)] };
+        let mut table = Table {
+            data,
+            dtz,
+            symmetric: material.counts[0] == material.counts[1],
+            has_pawns,
+            has_unique_pieces: material.counts.iter().any(|side| side[1..].contains(&1)),
+            pawn_count,
+            pairs: (0..if dtz { 1 } else { 2 }).map(|_| (0..4).map(|_| PairsData::default()).collect()).collect(),
+            map: 0,
+        };
+        let flags = *table.data.get(4)?;
+        if (flags & HAS_PAWNS != 0) != has_pawns || (flags & SPLIT != 0) == table.symmetric {
+            return None;
+        }
+
+        let sides = if !dtz && !table.symmetric { 2 } else { 1 };
+        let files = if has_pawns { 4 } else { 1 };
+        // pawns on both sides
+        let pp = has_pawns && pawn_count[1] > 0;
+        let mut at = 5;
+        for file in 0..files {
+            let first = *table.data.get(at)?;
+            let second = if pp { *table.data.get(at + 1)? } else { 0xff };
+            let order = [[first & 0xf, second & 0xf], [first >> 4, second >> 4]];
+            at += 1 + pp as usize;
+            for k in 0..material.pieces() {
+                let byte = *table.data.get(at)?;
+                for side in 0..sides {
+                    table.pairs[side][file].pieces[k] = if side == 1 { byte >> 4 } else { byte & 0xf };
+                }
+                at += 1;
+            }
+            for (side, order) in order.into_iter().enumerate().take(sides) {
+                table.set_groups(side, file, material.pieces(), order);
+            }
+        }
+        at += at & 1;
+
+        for file in 0..files {
+            for side in 0..sides {
+                at = table.set_sizes(side, file, at)?;
+            }
+        }
+        if dtz {
+            table.map = at;
+            for file in 0..files {
+                let flags = table.pairs[0][file].flags;
+                if flags & MAPPED == 0 {
+                    continue;
+                }
+                for i in 0..4 {
+                    if flags & WIDE != 0 {
+                        at += at & 1;
+                        table.pairs[0][file].map_idx[i] = (at - table.map) / 2 + 1;
+                        at += 2 * le16(&table.data, at) + 2;
+                    } else {
+                        table.pairs[0][file].map_idx[i] = at - table.map + 1;
+                        at += *table.data.get(at)? as usize + 1;
+                    }
+                }
+            }
+            at += at & 1;
+        }
+        for file in 0..files {
+            for side in 0..sides {
+                let d = &mut table.pairs[side][file];
+                d.sparse_index = at;
+                at += d.sparse_index_size * 6;
+            }
+        }
+        for file in 0..files {
+            for side in 0..sides {
+                let d = &mut table.pairs[side][file];
+                d.block_lengths = at;
+                at += d.block_lengths_size * 2;
+            }
+        }
+        for file in 0..files {
+            for side in 0..sides {
+                let d = &mut table.pairs[side][file];
+                // the blocks are aligned to 64 bytes
+                at = (at + 0x3f) & !0x3f;
+                d.data = at;
+                at += d.num_blocks * d.block_size;
+            }
+        }
+        (at <= table.data.len()).then_some(table)
+    }
+
+    /// splits the pieces into the groups encoded together and computes the factor of each group in the index
+    fn set_groups(&mut self, side: usize, file: usize, pieces: usize, order: [u8; 2]) {
+        let encoding = encoding();
+        let (has_pawns, has_unique_pieces) = (self.has_pawns, self.has_unique_pieces);
+        let pp = has_pawns && self.pawn_count[1] > 0;
+        let d = &mut self.pairs[side][file];
+        // the leading group has the kings and the unique piece, or the leading pawns
+        let mut first_len = if has_pawns { 0 } else if has_unique_pieces { 3 } else { 2 };
+        let mut n = 0;
+        d.group_len[0] = 1;
+        for i in 1..pieces {
+            first_len -= 1;
+            if first_len > 0 || d.pieces[i] == d.pieces[i - 1] {
+                d.group_len[n] += 1;
+            } else {
+                n += 1;
+                d.group_len[n] = 1;
+            }
+        }
+        n += 1;
+        d.group_len[n] = 0;
+
+        // the groups are encoded in the order the file gives, the leading one at order[0], the remaining pawns at order[1]
+        let mut next = if pp { 2 } else { 1 };
+        let mut free_squares = 64 - d.group_len[0] - if pp { d.group_len[1] } else { 0 };
+        let mut idx: u64 = 1;
+        let mut k = 0;
+        while next < n || k == order[0] as usize || k == order[1] as usize {
+            if k == order[0] as usize {
+                d.group_idx[0] = idx;
+                idx *= if has_pawns {
+                    encoding.lead_pawns_size[d.group_len[0]][file]
+                } else if has_unique_pieces {
+                    31332
+                } else {
+                    462
+                };
+            } else if k == order[1] as usize {
+                d.group_idx[1] = idx;
+                idx *= encoding.binomial[d.group_len[1]][48 - d.group_len[0]];
+            } else {
+                d.group_idx[next] = idx;
+                idx *= encoding.binomial[d.group_len[next]][free_squares];
+                free_squares -= d.group_len[next];
+                next += 1;
+            }
+            k += 1;
+        }
+        d.group_idx[n] = idx;
+    }
+
+    /// reads the sizes of the blocks and the Huffman codes, returns where the next table's start
+    fn set_sizes(&mut self, side: usize, file: usize, mut at: usize) -> Option<usize> {
+        let data = &self.data;
+        let d = &mut self.pairs[side][file];
+        d.flags = *data.get(at)?;
+        if d.flags & SINGLE_VALUE != 0 {
+            d.min_sym_len = *data.get(at + 1)?;
+            return Some(at + 2);
+        }
+        let groups = d.group_len.iter().position(|len| *len == 0)?;
+        let size = d.group_idx[groups];
+        d.block_size = 1 << data.get(at + 1)?;
+        d.span = 1 << data.get(at + 2)?;
+        d.sparse_index_size = size.div_ceil(d.span) as usize;
+        let padding = *data.get(at + 3)? as usize;
+        d.num_blocks = le32(data, at + 4);
+        d.block_lengths_size = d.num_blocks + padding;
+        let max_sym_len = *data.get(at + 8)?;
+        d.min_sym_len = *data.get(at + 9)?;
+        // the buffer of the decoder holds at least 32 bits
+        if d.min_sym_len == 0 || max_sym_len < d.min_sym_len || max_sym_len > 32 {
+            return None;
+        }
+        at += 10;
+        d.lowest_sym = at;
+
+        // the canonical codes are ordered so that longer codes have lower values, base64[i] is the lowest code of
+        // the length min_sym_len + i padded to 64 bits
+        let lengths = (max_sym_len - d.min_sym_len) as usize + 1;
+        d.base64 = vec![0; lengths];
+        for i in (0..lengths - 1).rev() {
+            let lowest = le16(data, d.lowest_sym + 2 * i) as u64;
+            let next_lowest = le16(data, d.lowest_sym + 2 * i + 2) as u64;
+            d.base64[i] = d.base64[i + 1].wrapping_add(lowest).wrapping_sub(next_lowest) / 2;
+        }
+        for (i, base) in d.base64.iter_mut().enumerate() {
+            *base = base.checked_shl(64 - i as u32 - d.min_sym_len as u32).unwrap_or(0);
+        }
+        at += lengths * 2;
+
+        // every symbol is a value or a pair of two other symbols, 3 bytes each
+        let symbols = le16(data, at);
+        at += 2;
+        d.btree = at;
+        if at + symbols * 3 > data.len() {
+            return None;
+        }
+        d.symlen = vec![0; symbols];
+        let mut visited = vec![false; symbols];
+        for sym in 0..symbols {
+            if !visited[sym] {
+                let len = symbol_length(data, d.btree, sym, &mut d.symlen, &mut visited);
+                d.symlen[sym] = len;
+            }
+        }
+        Some(at + symbols * 3 + (symbols & 1))
+    }
+
+    fn get(&self, side: usize, file: usize) -> &PairsData {
+        &self.pairs[side % self.pairs.len()][if self.has_pawns { file } else { 0 }]
+    }
+
+    /// the value stored for the position: the result with a WDL table, the distance to the next capture or pawn
+    /// move in plies with a DTZ table, which needs the result of the position
+    fn probe(&self, board: &Board, color: Color, black_stronger: bool, wdl: i32) -> Result<i32, Probe> {
+        let encoding = encoding();
+        // the tables store the positions with the stronger side as white, and symmetric ones with white to move,
+        // other positions are looked up with the colors swapped and the board mirrored
+        let flip = black_stronger || self.symmetric && color == Color::Black;
+        let (flip_color, flip_squares) = if flip { (8, 56) } else { (0, 0) };
+        let side = (flip as usize) ^ (color == Color::Black) as usize;
+
+        let mut pieces_on_board: Vec<(usize, u8)> =
+            board.board_array.iter().flatten().flatten().map(|p| (square(p.position), piece_code(p.variant, p.color))).collect();
+        pieces_on_board.sort();
+        let mut squares = [0; MAX_PIECES];
+        let mut pieces = [0; MAX_PIECES];
+        let mut size = 0;
+        let mut lead_pawns = 0;
+        let mut file = 0;
+        // with pawns there is a table for each file of the leading pawn, a to d
+        let lead = self.pairs[0][0].pieces[0] ^ flip_color;
+        if self.has_pawns {
+            for &(s, _) in pieces_on_board.iter().filter(|(_, code)| *code == lead) {
+                squares[size] = s ^ flip_squares;
+                size += 1;
+            }
+            lead_pawns = size;
+            let leader = (0..lead_pawns).max_by_key(|i| encoding.map_pawns[squares[*i]]).ok_or(Probe::Fail)?;
+            squares.swap(0, leader);
+            file = file_of(squares[0]).min(7 - file_of(squares[0]));
+        }
+        if self.dtz && self.get(side, file).flags & STM != side as u8 && (!self.symmetric || self.has_pawns) {
+            return Err(Probe::ChangeSideToMove);
+        }
+        for &(s, code) in pieces_on_board.iter().filter(|(_, code)| !self.has_pawns || *code != lead) {
+            if size == MAX_PIECES {
+                return Err(Probe::Fail);
+            }
+            squares[size] = s ^ flip_squares;
+            pieces[size] = code ^ flip_color;
+            size += 1;
+        }
+
+        let d = self.get(side, file);
+        // the pieces in the order of the table
+        for i in lead_pawns..size.saturating_sub(1) {
+            if let Some(j) = (i + 1..size).find(|j| d.pieces[i] == pieces[*j]) {
+                pieces.swap(i, j);
+                squares.swap(i, j);
+            }
+        }
+        // the leading piece is mirrored onto the files a to d
+        if file_of(squares[0]) > 3 {
+            squares[..size].iter_mut().for_each(|s| *s ^= 7);
+        }
+
+        let mut idx;
+        if self.has_pawns {
+            idx = encoding.lead_pawn_idx[lead_pawns][squares[0]];
+            squares[1..lead_pawns].sort_by_key(|s| encoding.map_pawns[*s]);
+            for (i, s) in squares.iter().enumerate().take(lead_pawns).skip(1) {
+                idx += encoding.binomial[i][encoding.map_pawns[*s]];
+            }
+        } else {
+            // without pawns the leading piece is mirrored onto the ranks 1 to 4 and below the a1-h8 diagonal
+            if rank_of(squares[0]) > 3 {
+                squares[..size].iter_mut().for_each(|s| *s ^= 56);
+            }
+            for i in 0..d.group_len[0] {
+                if off_diagonal(squares[i]) == 0 {
+                    continue;
+                }
+                if off_diagonal(squares[i]) > 0 {
+                    squares[i..size].iter_mut().for_each(|s| *s = ((*s >> 3) | (*s << 3)) & 63);
+                }
+                break;
+            }
+            idx = if self.has_unique_pieces {
+                let [s0, s1, s2] = [squares[0], squares[1], squares[2]];
+                let adjust1 = (s1 > s0) as usize;
+                let adjust2 = (s2 > s0) as usize + (s2 > s1) as usize;
+                let (r0, r1, r2) = (rank_of(s0), rank_of(s1), rank_of(s2));
+                (if off_diagonal(s0) != 0 {
+                    (encoding.map_a1d1d4[s0] * 63 + s1 - adjust1) * 62 + s2 - adjust2
+                } else if off_diagonal(s1) != 0 {
+                    (6 * 63 + r0 * 28 + encoding.map_b1h1h7[s1] as usize) * 62 + s2 - adjust2
+                } else if off_diagonal(s2) != 0 {
+                    6 * 63 * 62 + 4 * 28 * 62 + r0 * 7 * 28 + (r1 - adjust1) * 28 + encoding.map_b1h1h7[s2] as usize
+                } else {
+                    6 * 63 * 62 + 4 * 28 * 62 + 4 * 7 * 28 + r0 * 7 * 6 + (r1 - adjust1) * 6 + r2 - adjust2
+                }) as u64
+            } else {
+                encoding.map_kk[encoding.map_a1d1d4[squares[0]]][squares[1]]
+            };
+        }
+
+        // the other groups by the squares left to them, the remaining pawns can't stand on the 1st rank
+        idx *= d.group_idx[0];
+        let mut start = d.group_len[0];
+        let mut remaining_pawns = self.has_pawns && self.pawn_count[1] > 0;
+        let mut next = 1;
+        while d.group_len[next] != 0 {
+            let len = d.group_len[next];
+            squares[start..start + len].sort();
+            let mut n = 0;
+            for i in 0..len {
+                let s = squares[start + i];
+                let adjust = squares[..start].iter().filter(|other| s > **other).count();
+                n += encoding.binomial[i + 1][s - adjust - if remaining_pawns { 8 } else { 0 }];
+            }
+            remaining_pawns = false;
+            idx += n * d.group_idx[next];
+            start += len;
+            next += 1;
+        }
+
+        let value = self.decompress(d, idx).ok_or(Probe::Fail)?;
+        Ok(self.map_score(file, value, wdl))
+    }
+
+    /// the value with the index, found through the sparse index, the lengths of the blocks and the Huffman codes
+    fn decompress(&self, d: &PairsData, idx: u64) -> Option<i32> {
+        if d.flags & SINGLE_VALUE != 0 {
+            return Some(d.min_sym_len as i32);
+        }
+        let data = &self.data;
+        // the sparse index points to the value in the middle of every span
+        let k = (idx / d.span) as usize;
+        if k >= d.sparse_index_size {
+            return None;
+        }
+        let mut block = le32(data, d.sparse_index + 6 * k);
+        let mut offset = le16(data, d.sparse_index + 6 * k + 4) as i64;
+        offset += (idx % d.span) as i64 - (d.span / 2) as i64;
+        // every block holds its length plus one values
+        let block_length = |block: usize| le16(data, d.block_lengths + 2 * block) as i64;
+        while offset < 0 {
+            block = block.checked_sub(1)?;
+            offset += block_length(block) + 1;
+        }
+        while offset > block_length(block) {
+            offset -= block_length(block) + 1;
+            block += 1;
+            if block >= d.block_lengths_size {
+                return None;
+            }
+        }
+
+        let mut at = d.data + block * d.block_size;
+        let mut buffer = be(data, at, 8);
+        at += 8;
+        let mut buffer_size = 64;
+        let mut sym;
+        loop {
+            // the length of the code at the start of the buffer, less min_sym_len
+            let mut len = 0;
+            while buffer < d.base64[len] {
+                len += 1;
+            }
+            sym = ((buffer - d.base64[len]) >> (64 - len - d.min_sym_len as usize)) as usize;
+            sym += le16(data, d.lowest_sym + 2 * len);
+            let values = *d.symlen.get(sym)? as i64 + 1;
+            if offset < values {
+                break;
+            }
+            offset -= values;
+            let len = len + d.min_sym_len as usize;
+            buffer <<= len;
+            buffer_size -= len;
+            if buffer_size <= 32 {
+                buffer_size += 32;
+                buffer |= be(data, at, 4) << (64 - buffer_size);
+                at += 4;
+            }
+        }
+        // the symbol stands for a pair of symbols, which is followed to the value
+        while d.symlen[sym] != 0 {
+            let left = tree_left(data, d.btree, sym);
+            let values = *d.symlen.get(left)? as i64 + 1;
+            if offset < values {
+                sym = left;
+            } else {
+                offset -= values;
+                sym = tree_right(data, d.btree, sym);
+            }
+            if sym >= d.symlen.len() {
+                return None;
+            }
+        }
+        Some(tree_left(data, d.btree, sym) as i32)
+    }
+
+    /// the result of a WDL value, the plies of a DTZ value, which counts moves in some tables
+    fn map_score(&self, file: usize, value: i32, wdl: i32) -> i32 {
+        if !self.dtz {
+            return value - 2;
+        }
+        // where the values of loss, blessed loss, cursed win and win start in the map
+        const WDL_MAP: [usize; 5] = [1, 3, 0, 2, 0];
+        let d = self.get(0, file);
+        let mut value = value;
+        if d.flags & MAPPED != 0 {
+            let idx = d.map_idx[WDL_MAP[(wdl + 2) as usize]] + value as usize;
+            value = if d.flags & WIDE != 0 {
+                le16(&self.data, self.map + 2 * idx) as i32
+            } else {
+                self.data.get(self.map + idx).copied().unwrap_or(0) as i32
+            };
+        }
+        let moves = match wdl {
+            WIN => d.flags & WIN_PLIES == 0,
+            LOSS => d.flags & LOSS_PLIES == 0,
+            _ => true,
+        };
+        if moves {
+            value *= 2;
+        }
+        value + 1
+    }
+}
+
+/// the number of values a symbol stands for, less one, computed for its children first
+fn symbol_length(data: &[u8], btree: usize, sym: usize, symlen: &mut [u8], visited: &mut [bool]) -> u8 {
+    visited[sym] = true;
+    let right = tree_right(data, btree, sym);
+    if right == 0xfff {
+        return 0;
+    }
+    let left = tree_left(data, btree, sym);
+    if left >= symlen.len() || right >= symlen.len() {
+        return 0;
+    }
+    for child in [left, right] {
+        if !visited[child] {
+            symlen[child] = symbol_length(data, btree, child, symlen, visited);
+        }
+    }
+    symlen[left].wrapping_add(symlen[right]).wrapping_add(1)
+}
+
+// the two children of a symbol with 12 bits each, the value in the left one for a symbol without children
+fn tree_left(data: &[u8], btree: usize, sym: usize) -> usize {
+    let at = btree + 3 * sym;
+    ((byte(data, at + 1) & 0xf) << 8) | byte(data, at)
+}
+
+fn tree_right(data: &[u8], btree: usize, sym: usize) -> usize {
+    let at = btree + 3 * sym;
+    (byte(data, at + 2) << 4) | (byte(data, at + 1) >> 4)
+}
+
+// the numbers of the files, bytes beyond the end read as 0
+fn byte(data: &[u8], at: usize) -> usize {
+    data.get(at).copied().unwrap_or(0) as usize
+}
+
+fn le16(data: &[u8], at: usize) -> usize {
+    byte(data, at) | byte(data, at + 1) << 8
+}
+
+fn le32(data: &[u8], at: usize) -> usize {
+    le16(data, at) | le16(data, at + 2) << 16
+}
+
+fn be(data: &[u8], at: usize, bytes: usize) -> u64 {
+    (0..bytes).fold(0, |value, i| (value << 8) | byte(data, at + i) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{Engine, TB_WIN_SCORE};
+    use crate::skill::Skill;
+
+    fn position(fen: &str) -> (Board, Color) {
+        let (placement, side) = fen.split_once(' ').unwrap();
+        (Board::from_fen(placement).unwrap(), if side == "w" { Color::White } else { Color::Black })
+    }
+
+    /// a directory with KQvK tables storing a single value for each side to move
+    fn single_value_tables(dir_name: &str) -> Tablebases {
+        let dir = std::env::temp_dir().join(format!("rusty-chess-{dir_name}-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        // the order of the groups, the pieces of both sides to move, padding, then the value of each side
+        let pieces = [0x66, 0x55, 0xee];
+        let mut wdl = [WDL_MAGIC.as_slice(), &[SPLIT, 0x00], &pieces, &[0], &[SINGLE_VALUE, 4, SINGLE_VALUE, 0]].concat();
+        // the DTZ table stores white to move, wins in 5 moves
+        let mut dtz = [DTZ_MAGIC.as_slice(), &[SPLIT, 0x00], &pieces, &[0], &[SINGLE_VALUE, 5]].concat();
+        wdl.resize(64, 0);
+        dtz.resize(64, 0);
+        fs::write(dir.join("KQvK.rtbw"), wdl).unwrap();
+        fs::write(dir.join("KQvK.rtbz"), dtz).unwrap();
+        Tablebases::open(&dir).unwrap()
+    }
+
+    #[test]
+    fn encoding_tables() {
+        let encoding = encoding();
+        let placements = encoding.map_kk.iter().flatten().max().unwrap() + 1;
+        assert_eq!(placements, 462);
+        // the triangle below the diagonal first, a1, b2, c3 and d4 last
+        assert_eq!(encoding.map_a1d1d4[1], 0);
+        assert_eq!([0, 9, 18, 27].map(|s| encoding.map_a1d1d4[s]), [6, 7, 8, 9]);
+        assert_eq!(encoding.map_b1h1h7.iter().max(), Some(&27));
+        // a2, h2, a3 and so on
+        assert_eq!([8, 15, 16].map(|s| encoding.map_pawns[s]), [47, 46, 45]);
+        assert_eq!(encoding.binomial[2][5], 10);
+        assert_eq!(encoding.binomial[0][63], 1);
+        assert_eq!(encoding.lead_pawns_size[1][0], 6);
+    }
+
+    #[test]
+    fn parses_names() {
+        let material = Material::parse("KRPvKR").unwrap();
+        assert_eq!(material.pieces(), 5);
+        assert_eq!((material.pawns(0), material.pawns(1)), (1, 0));
+        assert!(Material::parse("KRvR").is_none());
+        assert!(Material::parse("KvK").is_none());
+        assert!(Material::parse("kqvk").is_none());
+    }
+
+    #[test]
+    fn probes_both_sides_and_colors() {
+        let tables = single_value_tables("wdl");
+        assert_eq!(tables.max_pieces(), 3);
+        let (board, color) = position("4k3/8/8/8/8/8/8/3QK3 w");
+        assert_eq!(tables.probe_wdl(&board, color), Some(Wdl::Win));
+        assert_eq!(tables.probe_wdl(&board, color.opponent()), Some(Wdl::Loss));
+        // black with the queen is looked up with the colors swapped
+        let (board, color) = position("3qk3/8/8/8/8/8/8/4K3 b");
+        assert_eq!(tables.probe_wdl(&board, color), Some(Wdl::Win));
+        assert_eq!(tables.probe_wdl(&board, color.opponent()), Some(Wdl::Loss));
+        // the king captures the queen and draws
+        let (board, color) = position("4k3/4Q3/8/8/8/8/8/K7 b");
+        assert_eq!(tables.probe_wdl(&board, color), Some(Wdl::Draw));
+        // a king which can be captured is no position of chess
+        let (board, color) = position("4k3/4Q3/8/8/8/8/8/K7 w");
+        assert_eq!(tables.probe_wdl(&board, color), None);
+        let (board, color) = position("4k3/8/8/8/8/8/8/R3K3 w");
+        assert_eq!(tables.probe_wdl(&board, color), None);
+    }
+
+    #[test]
+    fn probes_distances() {
+        let tables = single_value_tables("dtz");
+        let (board, color) = position("4k3/8/8/8/8/8/8/3QK3 w");
+        // 5 moves are 10 plies, plus one
+        assert_eq!(tables.probe_dtz(&board, color), Some(11));
+        // the table has no values for black to move, they are found one ply ahead
+        assert_eq!(tables.probe_dtz(&board, color.opponent()), Some(-12));
+        // one more ply for the move itself, a queen left to the king draws
+        let ranked = tables.rank(&board, color).unwrap();
+        assert_eq!(ranked[0].1, 13);
+        let hanging = Move::from_uci("d1d7").unwrap();
+        assert!(ranked.contains(&(hanging, 0)));
+    }
+
+    #[test]
+    fn engine_plays_from_the_tables() {
+        let tables = Arc::new(single_value_tables("engine"));
+        let (board, color) = position("4k3/8/8/8/8/8/8/3QK3 w");
+        let mut engine = Engine::new(4, 1);
+        engine.tablebases = Some(tables.clone());
+        let result = engine.search(&board, color, None);
+        // decided at the root without a search, the line follows the tables
+        assert_eq!((result.tablebase, result.score, result.nodes), (Some(13), TB_WIN_SCORE - 13, 0));
+        assert_eq!(result.pv.first(), result.best_move.as_ref());
+        // inside the search the capture of the rook reaches a won position
+        let (board, color) = position("4k3/8/8/8/8/8/3r4/3QK3 w");
+        let result = engine.search(&board, color, None);
+        assert_eq!((result.tablebase, result.score), (None, TB_WIN_SCORE - 1));
+        // a weakened engine searches
+        let (board, color) = position("4k3/8/8/8/8/8/8/3QK3 w");
+        engine.skill = Skill::new(1);
+        assert_eq!(engine.search(&board, color, None).tablebase, None);
+    }
+}
@@ -0,0 +1,299 @@
+use crossterm::style::Color;
+use std::env;
+
+/// amount of colors the terminal is able to display
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum ColorDepth {
+    TrueColor,
+    Ansi256,
+    Ansi16,
+}
+
+impl ColorDepth {
+    /// guesses the color support of the terminal from the environment variables it sets
+    pub fn detect() -> ColorDepth {
+        let colorterm = env::var("COLORTERM").unwrap_or_default().to_lowercase();
+        let term = env::var("TERM").unwrap_or_default().to_lowercase();
+        if colorterm == "truecolor" || colorterm == "24bit" || env::var("WT_SESSION").is_ok() {
+            ColorDepth::TrueColor
+        } else if term.contains("256color") {
+            ColorDepth::Ansi256
+        } else {
+            ColorDepth::Ansi16
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<ColorDepth> {
+        match name.to_lowercase().as_str() {
+            "truecolor" | "24bit" => Some(ColorDepth::TrueColor),
+            "256" => Some(ColorDepth::Ansi256),
+            "16" => Some(ColorDepth::Ansi16),
+            _ => None,
+        }
+    }
+}
+
+/// the colors used to draw the board, every color is defined as rgb and reduced to the color depth of the terminal
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Theme {
+    pub name: &'static str,
+    pub light_square: Color,
+    pub dark_square: Color,
+    pub last_move_light: Color,
+    pub last_move_dark: Color,
+    pub check: Color,
+    pub move_highlight: Color,
+    /// the squares of a move suggested as hint
+    pub hint: Color,
+    pub cursor: Color,
+    /// the pieces of both sides, which differ by their glyphs
+    pub piece: Color,
+    pub border: Color,
+    pub label: Color,
+    pub frame: Color,
+    /// the share of the evaluation bar held by white and by black
+    pub eval_white: Color,
+    pub eval_black: Color,
+}
+
+const fn rgb(r: u8, g: u8, b: u8) -> Color {
+    Color::Rgb { r, g, b }
+}
+
+pub const GREEN: Theme = Theme {
+    name: "green",
+    light_square: rgb(234, 234, 234),
+    dark_square: rgb(118, 147, 84),
+    last_move_light: rgb(246, 246, 130),
+    last_move_dark: rgb(186, 202, 68),
+    check: rgb(214, 64, 52),
+    move_highlight: rgb(249, 215, 0),
+    hint: rgb(82, 176, 220),
+    cursor: rgb(205, 0, 0),
+    piece: rgb(0, 0, 0),
+    border: rgb(128, 128, 128),
+    label: rgb(255, 255, 255),
+    frame: rgb(0, 0, 238),
+    eval_white: rgb(240, 240, 240),
+    eval_black: rgb(40, 40, 40),
+};
+
+pub const BROWN: Theme = Theme {
+    name: "brown",
+    light_square: rgb(240, 217, 181),
+    dark_square: rgb(181, 136, 99),
+    last_move_light: rgb(205, 210, 106),
+    last_move_dark: rgb(170, 162, 58),
+    check: rgb(214, 64, 52),
+    move_highlight: rgb(249, 215, 0),
+    hint: rgb(82, 176, 220),
+    cursor: rgb(205, 0, 0),
+    piece: rgb(0, 0, 0),
+    border: rgb(102, 68, 46),
+    label: rgb(240, 217, 181),
+    frame: rgb(102, 68, 46),
+    eval_white: rgb(250, 240, 225),
+    eval_black: rgb(60, 40, 28),
+};
+
+pub const BLUE: Theme = Theme {
+    name: "blue",
+    light_square: rgb(222, 227, 230),
+    dark_square: rgb(140, 162, 173),
+    last_move_light: rgb(195, 216, 135),
+    last_move_dark: rgb(151, 172, 95),
+    check: rgb(214, 64, 52),
+    move_highlight: rgb(249, 215, 0),
+    hint: rgb(110, 190, 110),
+    cursor: rgb(205, 0, 0),
+    piece: rgb(0, 0, 0),
+    border: rgb(75, 100, 120),
+    label: rgb(255, 255, 255),
+    frame: rgb(0, 0, 238),
+    eval_white: rgb(235, 240, 245),
+    eval_black: rgb(35, 45, 55),
+};
+
+pub const HIGH_CONTRAST: Theme = Theme {
+    name: "high-contrast",
+    light_square: rgb(255, 255, 255),
+    dark_square: rgb(0, 170, 170),
+    last_move_light: rgb(0, 255, 255),
+    last_move_dark: rgb(0, 205, 205),
+    check: rgb(255, 0, 0),
+    move_highlight: rgb(255, 255, 0),
+    hint: rgb(0, 255, 0),
+    cursor: rgb(255, 0, 255),
+    piece: rgb(0, 0, 0),
+    border: rgb(0, 0, 0),
+    label: rgb(255, 255, 255),
+    frame: rgb(0, 0, 0),
+    eval_white: rgb(255, 255, 255),
+    eval_black: rgb(0, 0, 0),
+};
+
+pub const MONOCHROME: Theme = Theme {
+    name: "monochrome",
+    light_square: rgb(238, 238, 238),
+    dark_square: rgb(168, 168, 168),
+    last_move_light: rgb(208, 208, 208),
+    last_move_dark: rgb(138, 138, 138),
+    check: rgb(88, 88, 88),
+    move_highlight: rgb(255, 255, 255),
+    hint: rgb(108, 108, 108),
+    cursor: rgb(58, 58, 58),
+    piece: rgb(0, 0, 0),
+    border: rgb(88, 88, 88),
+    label: rgb(255, 255, 255),
+    frame: rgb(28, 28, 28),
+    eval_white: rgb(255, 255, 255),
+    eval_black: rgb(28, 28, 28),
+};
+
+pub const BUILT_IN_THEMES: [Theme; 5] = [GREEN, BROWN, BLUE, HIGH_CONTRAST, MONOCHROME];
+
+// the 16 ansi colors with the rgb values of the xterm default palette
+const ANSI_16: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::DarkRed, (205, 0, 0)),
+    (Color::DarkGreen, (0, 205, 0)),
+    (Color::DarkYellow, (205, 205, 0)),
+    (Color::DarkBlue, (0, 0, 238)),
+    (Color::DarkMagenta, (205, 0, 205)),
+    (Color::DarkCyan, (0, 205, 205)),
+    (Color::Grey, (229, 229, 229)),
+    (Color::DarkGrey, (127, 127, 127)),
+    (Color::Red, (255, 0, 0)),
+    (Color::Green, (0, 255, 0)),
+    (Color::Yellow, (255, 255, 0)),
+    (Color::Blue, (92, 92, 255)),
+    (Color::Magenta, (255, 0, 255)),
+    (Color::Cyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+impl Theme {
+    /// looks up one of the built-in themes by its name
+    pub fn by_name(name: &str) -> Option<Theme> {
+        BUILT_IN_THEMES.into_iter().find(|t| t.name.eq_ignore_ascii_case(name))
+    }
+
+    /// returns the built-in theme following this one, used to cycle through the themes at runtime
+    pub fn next(&self) -> Theme {
+        let index = BUILT_IN_THEMES.iter().position(|t| t.name == self.name).unwrap_or(0);
+        BUILT_IN_THEMES[(index + 1) % BUILT_IN_THEMES.len()]
+    }
+
+    /// overrides a single color of the theme by the name it has in the config file
+    pub fn set_color(&mut self, key: &str, color: Color) -> Result<(), String> {
+        let field = match key {
+            "light_square" => &mut self.light_square,
+            "dark_square" => &mut self.dark_square,
+            "last_move_light" => &mut self.last_move_light,
+            "last_move_dark" => &mut self.last_move_dark,
+            "check" => &mut self.check,
+            "move_highlight" => &mut self.move_highlight,
+            "hint" => &mut self.hint,
+            "cursor" => &mut self.cursor,
+            "piece" => &mut self.piece,
+            "border" => &mut self.border,
+            "label" => &mut self.label,
+            "frame" => &mut self.frame,
+            "eval_white" => &mut self.eval_white,
+            "eval_black" => &mut self.eval_black,
+            _ => return Err(format!("unknown theme color '{key}'")),
+        };
+        *field = color;
+        Ok(())
+    }
+
+    /// the theme with the colors of the config file, which replace the colors of every theme
+    pub fn with_overrides(mut self, overrides: &[(String, Color)]) -> Theme {
+        for (key, color) in overrides {
+            // the keys have been checked when the config was loaded
+            let _ = self.set_color(key, *color);
+        }
+        self
+    }
+
+    /// reduces every color of the theme to the nearest color the terminal is able to display
+    pub fn for_color_depth(&self, depth: ColorDepth) -> Theme {
+        let reduce = |c: Color| reduce_color(c, depth);
+        Theme {
+            name: self.name,
+            light_square: reduce(self.light_square),
+            dark_square: reduce(self.dark_square),
+            last_move_light: reduce(self.last_move_light),
+            last_move_dark: reduce(self.last_move_dark),
+            check: reduce(self.check),
+            move_highlight: reduce(self.move_highlight),
+            hint: reduce(self.hint),
+            cursor: reduce(self.cursor),
+            piece: reduce(self.piece),
+            border: reduce(self.border),
+            label: reduce(self.label),
+            frame: reduce(self.frame),
+            eval_white: reduce(self.eval_white),
+            eval_black: reduce(self.eval_black),
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        GREEN
+    }
+}
+
+/// parses a color written as hex code, e.g. `#769354`
+pub fn parse_hex_color(value: &str) -> Option<Color> {
+    let hex = value.strip_prefix('#')?;
+    if hex.len() != 6 {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    Some(rgb(channel(0)?, channel(2)?, channel(4)?))
+}
+
+fn reduce_color(color: Color, depth: ColorDepth) -> Color {
+    let Color::Rgb { r, g, b } = color else {
+        return color;
+    };
+    match depth {
+        ColorDepth::TrueColor => color,
+        ColorDepth::Ansi256 => Color::AnsiValue(rgb_to_ansi_256(r, g, b)),
+        ColorDepth::Ansi16 => ANSI_16
+            .iter()
+            .min_by_key(|(_, c)| distance((r, g, b), *c))
+            .map(|(c, _)| *c)
+            .unwrap_or(color),
+    }
+}
+
+/// maps a rgb color onto the 6x6x6 color cube or the grayscale ramp of the 256 color palette
+fn rgb_to_ansi_256(r: u8, g: u8, b: u8) -> u8 {
+    const CUBE_STEPS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+    let nearest_step = |v: u8| {
+        (0..6)
+            .min_by_key(|i| (CUBE_STEPS[*i] as i32 - v as i32).abs())
+            .unwrap_or(0)
+    };
+    let (ri, gi, bi) = (nearest_step(r), nearest_step(g), nearest_step(b));
+    let cube_color = (CUBE_STEPS[ri], CUBE_STEPS[gi], CUBE_STEPS[bi]);
+    let cube_index = 16 + 36 * ri + 6 * gi + bi;
+
+    let average = (r as u32 + g as u32 + b as u32) / 3;
+    let gray_index = (average.saturating_sub(3) / 10).min(23) as u8;
+    let gray_value = 8 + gray_index * 10;
+
+    if distance((r, g, b), (gray_value, gray_value, gray_value)) < distance((r, g, b), cube_color) {
+        232 + gray_index
+    } else {
+        cube_index as u8
+    }
+}
+
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let d = |x: u8, y: u8| (x as i32 - y as i32).pow(2) as u32;
+    d(a.0, b.0) + d(a.1, b.1) + d(a.2, b.2)
+}
@@ -0,0 +1,38 @@
+use std::time::Duration;
+
+// kept back from every move for the time between the end of the search and the clock being stopped
+const MOVE_OVERHEAD: Duration = Duration::from_millis(50);
+// without moves to go, the remaining time is planned as if this many moves were left
+const DEFAULT_MOVES_TO_GO: u32 = 30;
+// never plan more than this share of the remaining time for a single move
+const MAX_SOFT_SHARE: f64 = 0.5;
+const MAX_HARD_SHARE: f64 = 0.8;
+// the hard limit is this many times the soft one, if the remaining time allows it
+const HARD_FACTOR: u32 = 4;
+
+/// how long the engine may think about a move
+///
+/// a new iteration is only started within the soft limit, which grows while the search is unsure;
+/// at the hard limit the search is stopped in any case
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct TimeLimits {
+    pub soft: Duration,
+    pub hard: Duration,
+}
+
+impl TimeLimits {
+    /// limits from the remaining time on the clock, the increment per move and the moves until the next time control
+    pub fn new(remaining: Duration, increment: Duration, moves_to_go: Option<u32>) -> Self {
+        let available = remaining.saturating_sub(MOVE_OVERHEAD);
+        let moves_to_go = moves_to_go.unwrap_or(DEFAULT_MOVES_TO_GO).max(1);
+        let soft = (available / moves_to_go + increment * 3 / 4).min(available.mul_f64(MAX_SOFT_SHARE));
+        let hard = (soft * HARD_FACTOR).min(available.mul_f64(MAX_HARD_SHARE)).max(soft);
+        TimeLimits { soft, hard }
+    }
+
+    /// exactly the given time per move, as with `go movetime`
+    pub fn fixed(time: Duration) -> Self {
+        let time = time.saturating_sub(MOVE_OVERHEAD);
+        TimeLimits { soft: time, hard: time }
+    }
+}
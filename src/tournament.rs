@@ -0,0 +1,251 @@
+use crate::board::Move;
+use crate::clock::Clock;
+use crate::config::Config;
+use crate::console::DisplaySettings;
+use crate::game::{self, Game, GameResult};
+use crate::i18n::{self, tr, Message};
+use crate::notation::{self, parse_san};
+use crate::pieces::Color;
+use crate::player::{Player, Seat};
+use crate::renderer::TextRenderer;
+use crate::theme::ColorDepth;
+use crate::ui::Ui;
+
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+/// a position the games of a match start from, each opening is played twice with swapped colors
+struct Opening {
+    fen: String,
+    moves: Vec<Move>,
+}
+
+/// settings of `rusty-chess match`, all other arguments are passed on to the config
+struct MatchSettings {
+    engines: [Seat; 2],
+    games: u32,
+    openings: Option<PathBuf>,
+    pgn: Option<PathBuf>,
+    max_moves: u32,
+}
+
+/// wins, draws and losses of the first engine
+#[derive(Default)]
+struct Score {
+    wins: u32,
+    draws: u32,
+    losses: u32,
+}
+
+/// plays two engines against each other without drawing a board and prints the score:
+/// ```text
+/// rusty-chess match --engine1 engine --engine2 uci:/usr/bin/stockfish --games 10 --time 1+0 --pgn match.pgn
+/// ```
+pub fn run(args: &[String]) -> Result<(), String> {
+    let (settings, config_args) = MatchSettings::parse(args)?;
+    let config = Config::load(&config_args)?;
+    i18n::set_language(config.language);
+
+    let openings = match &settings.openings {
+        Some(path) => load_openings(path)?,
+        None => vec![Opening { fen: Game::new(None).start_fen, moves: vec![] }],
+    };
+    if openings.is_empty() {
+        return Err("the opening file contains no positions".to_string());
+    }
+    let mut pgn_file = settings
+        .pgn
+        .as_ref()
+        .map(|path| File::create(path).map_err(|e| format!("could not create {}: {e}", path.display())))
+        .transpose()?;
+
+    // the colors are only used by network players, which can't take part in a match
+    let mut players: [Box<dyn Player>; 2] = [
+        create_engine(&settings.engines[0], &config)?,
+        create_engine(&settings.engines[1], &config)?,
+    ];
+    let names = [players[0].name(), players[1].name()];
+    // the game loop needs a screen, nothing of it is shown
+    let mut ui = Ui::new(Box::new(TextRenderer::new(io::sink())), DisplaySettings::default(), ColorDepth::Ansi16);
+
+    let mut score = Score::default();
+    for round in 0..settings.games {
+        let opening = &openings[(round / 2) as usize % openings.len()];
+        let swapped = round % 2 == 1;
+        let mut game = Game::from_fen(&opening.fen, config.time_control.map(Clock::new))?;
+        for mv in &opening.moves {
+            let _ = game.play(*mv);
+        }
+        game.max_moves = Some(settings.max_moves);
+
+        for player in players.iter_mut() {
+            player.new_game();
+        }
+        // the players are given as [white, black]
+        if swapped {
+            players.swap(0, 1);
+        }
+        let result = game::run(&mut game, &mut players, &mut ui, false);
+        if swapped {
+            players.swap(0, 1);
+        }
+
+        let (white, black) = if swapped { (&names[1], &names[0]) } else { (&names[0], &names[1]) };
+        let first_engine_color = if swapped { Color::Black } else { Color::White };
+        // a game ends aborted when the engine to move quits, which costs it the game
+        let winner = match result {
+            GameResult::Won(color, _) => Some(color),
+            GameResult::Aborted => Some(game.side_to_move.opponent()),
+            GameResult::Draw => None,
+        };
+        match winner {
+            Some(color) if color == first_engine_color => score.wins += 1,
+            Some(_) => score.losses += 1,
+            None => score.draws += 1,
+        }
+        println!(
+            "{} {white} - {black} {} {}",
+            tr(Message::MatchGame).replace("{}", &format!("{}/{}", round + 1, settings.games)),
+            result.pgn(),
+            result.describe().map(|text| format!("({text})")).unwrap_or_default()
+        );
+
+        if let Some(file) = pgn_file.as_mut() {
+            let tags = [
+                ("Event", "rusty-chess match".to_string()),
+                ("Site", "?".to_string()),
+                ("Date", notation::pgn_date()),
+                ("Round", (round + 1).to_string()),
+                ("White", white.clone()),
+                ("Black", black.clone()),
+            ];
+            file.write_all(notation::write_pgn(&game, &tags, result).as_bytes())
+                .map_err(|e| format!("could not write the PGN: {e}"))?;
+        }
+    }
+
+    print_score_table(&names, &score);
+    Ok(())
+}
+
+impl MatchSettings {
+    /// takes the arguments of the match, returns the remaining ones
+    fn parse(args: &[String]) -> Result<(MatchSettings, Vec<String>), String> {
+        let mut settings = MatchSettings {
+            engines: [Seat::Engine, Seat::Engine],
+            games: 2,
+            openings: None,
+            pgn: None,
+            max_moves: 200,
+        };
+        let mut rest = vec![];
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or(format!("{arg} expects a value"));
+            match arg.as_str() {
+                "--engine1" | "--engine2" => {
+                    let name = value()?;
+                    let seat = Seat::from_name(name).ok_or(format!("unknown engine '{name}', expected engine or uci:<path>"))?;
+                    settings.engines[if arg == "--engine1" { 0 } else { 1 }] = seat;
+                }
+                "--games" => settings.games = parse_number(arg, value()?)?,
+                "--openings" => settings.openings = Some(PathBuf::from(value()?)),
+                "--pgn" => settings.pgn = Some(PathBuf::from(value()?)),
+                "--max-moves" => settings.max_moves = parse_number(arg, value()?)?,
+                _ => rest.push(arg.clone()),
+            }
+        }
+        Ok((settings, rest))
+    }
+}
+
+fn parse_number(arg: &str, value: &str) -> Result<u32, String> {
+    match value.parse() {
+        Ok(number) if number > 0 => Ok(number),
+        _ => Err(format!("{arg} expects a positive number, got '{value}'")),
+    }
+}
+
+fn create_engine(seat: &Seat, config: &Config) -> Result<Box<dyn Player>, String> {
+    match seat {
+        Seat::Engine | Seat::Uci(_) => seat.create(Color::White, config).map_err(|e| e.to_string()),
+        _ => Err("only engine and uci:<path> can play a match".to_string()),
+    }
+}
+
+/// reads the openings from a PGN file or from a file with one position per line in FEN or EPD
+fn load_openings(path: &PathBuf) -> Result<Vec<Opening>, String> {
+    let content = fs::read_to_string(path).map_err(|e| format!("could not read {}: {e}", path.display()))?;
+    if path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("pgn")) {
+        return notation::parse_pgn(&content)
+            .into_iter()
+            .map(|pgn| {
+                let fen = pgn.tag("FEN").map(str::to_string).unwrap_or(Game::new(None).start_fen);
+                let mut game = Game::from_fen(&fen, None)?;
+                // the opening ends before the first move the rules of this game don't know, e.g. castling
+                for san in &pgn.moves {
+                    let Some(mv) = parse_san(&game.board, game.side_to_move, san) else {
+                        break;
+                    };
+                    let _ = game.play(mv);
+                }
+                Ok(Opening { fen, moves: game.moves })
+            })
+            .collect();
+    }
+
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            // EPD has the first four fields of FEN followed by operations
+            let fields: Vec<&str> = line.split_whitespace().take(4).collect();
+            let fen = format!("{} 0 1", fields.join(" "));
+            Game::from_fen(&fen, None)?;
+            Ok(Opening { fen, moves: vec![] })
+        })
+        .collect()
+}
+
+fn print_score_table(names: &[String; 2], score: &Score) {
+    let games = score.wins + score.draws + score.losses;
+    let points = |wins: u32| wins as f64 + score.draws as f64 / 2.0;
+    let [engine, total, wins, draws, losses] =
+        [Message::MatchEngine, Message::MatchScore, Message::MatchWins, Message::MatchDraws, Message::MatchLosses].map(tr);
+    // the columns are at least as wide as their translated headings
+    let column = |heading: &str, width: usize| width.max(heading.chars().count());
+    let width = column(engine, names.iter().map(String::len).max().unwrap_or(0));
+    let (total_width, wins_width, draws_width, losses_width) = (column(total, 9), column(wins, 5), column(draws, 5), column(losses, 5));
+    println!();
+    println!("{engine:width$}  {total:>total_width$}  {wins:>wins_width$}  {draws:>draws_width$}  {losses:>losses_width$}");
+    for (name, wins, losses) in [(&names[0], score.wins, score.losses), (&names[1], score.losses, score.wins)] {
+        let total = format!("{}/{games}", points(wins));
+        println!(
+            "{name:width$}  {total:>total_width$}  {wins:>wins_width$}  {:>draws_width$}  {losses:>losses_width$}",
+            score.draws
+        );
+    }
+    if let Some((difference, margin)) = elo_difference(score) {
+        println!();
+        println!("{}", tr(Message::MatchElo).replace("{}", &format!("{difference:+.1} +/- {margin:.1}")));
+    }
+}
+
+/// the Elo difference of the first engine and its error margin, based on the variance of the single results
+fn elo_difference(score: &Score) -> Option<(f64, f64)> {
+    let games = (score.wins + score.draws + score.losses) as f64;
+    if games == 0.0 {
+        return None;
+    }
+    let (wins, draws, losses) = (score.wins as f64, score.draws as f64, score.losses as f64);
+    let mean = (wins + draws / 2.0) / games;
+    let variance = (wins * (1.0 - mean).powi(2) + draws * (0.5 - mean).powi(2) + losses * mean.powi(2)) / games;
+    let margin = 1.96 * (variance / games).sqrt();
+    // a score of 0% or 100% is an infinite difference
+    let elo = |score: f64| -400.0 * (1.0 / score.clamp(0.0, 1.0) - 1.0).log10();
+    let error = (elo(mean + margin) - elo(mean - margin)) / 2.0;
+    // adding zero turns -0.0 into 0.0, the error of an infinite difference is infinite as well
+    Some((elo(mean) + 0.0, if error.is_nan() { f64::INFINITY } else { error }))
+}
@@ -0,0 +1,257 @@
+use crate::board::Move;
+use crate::engine::MATE_THRESHOLD;
+use crate::pieces::{PieceVariant, Position};
+
+use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+
+/// how a stored score relates to the real score of the position
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Bound {
+    Exact,
+    /// the search failed high, the real score is at least the stored one
+    Lower,
+    /// the search failed low, the real score is at most the stored one
+    Upper,
+}
+
+/// what the search has learned about a position
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Entry {
+    pub depth: u8,
+    pub bound: Bound,
+    /// score from the perspective of the side to move, mate scores are relative to the current ply
+    pub score: i32,
+    pub best_move: Option<Move>,
+}
+
+/// a fixed size table of search results, indexed by the Zobrist hash of the position
+///
+/// every slot is two atomic words: the data and the hash xor the data, a slot torn by two threads
+/// writing at once no longer matches its hash and is ignored
+pub struct TranspositionTable {
+    slots: Vec<[AtomicU64; 2]>,
+    // incremented by every search, entries of older searches are replaced first
+    generation: AtomicU8,
+}
+
+impl TranspositionTable {
+    /// a table using about the given number of megabytes
+    pub fn new(megabytes: usize) -> Self {
+        let count = (megabytes.max(1) * 1024 * 1024 / std::mem::size_of::<[AtomicU64; 2]>()).max(1);
+        TranspositionTable {
+            slots: (0..count).map(|_| [AtomicU64::new(0), AtomicU64::new(0)]).collect(),
+            generation: AtomicU8::new(0),
+        }
+    }
+
+    pub fn clear(&self) {
+        for [check, data] in &self.slots {
+            check.store(0, Ordering::Relaxed);
+            data.store(0, Ordering::Relaxed);
+        }
+        self.generation.store(0, Ordering::Relaxed);
+    }
+
+    /// called at the start of every search
+    pub fn new_search(&self) {
+        self.generation.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn probe(&self, hash: u64, ply: i32) -> Option<Entry> {
+        let [check, data] = &self.slots[self.index(hash)];
+        let data = data.load(Ordering::Relaxed);
+        if data == 0 || check.load(Ordering::Relaxed) ^ data != hash {
+            return None;
+        }
+        let mut entry = unpack(data);
+        entry.score = score_from_table(entry.score, ply);
+        Some(entry)
+    }
+
+    /// stores an entry, replacing the one in its slot unless that one is from this search and deeper
+    pub fn store(&self, hash: u64, ply: i32, mut entry: Entry) {
+        let [check, data] = &self.slots[self.index(hash)];
+        let generation = self.generation.load(Ordering::Relaxed) & GENERATION_MASK;
+        let old = data.load(Ordering::Relaxed);
+        let old_hash = check.load(Ordering::Relaxed) ^ old;
+        if old != 0 && old_hash != hash {
+            let (old_depth, old_generation) = ((old >> 32) as u8, ((old >> 42) as u8) & GENERATION_MASK);
+            if old_generation == generation && old_depth > entry.depth {
+                return;
+            }
+        }
+        // keep the best move of an earlier search of the same position if this one has none
+        if entry.best_move.is_none() && old != 0 && old_hash == hash {
+            entry.best_move = unpack(old).best_move;
+        }
+        entry.score = score_to_table(entry.score, ply);
+        let packed = pack(entry, generation);
+        data.store(packed, Ordering::Relaxed);
+        check.store(hash ^ packed, Ordering::Relaxed);
+    }
+
+    /// the permille of slots used by the current search, reported as `hashfull` in UCI
+    pub fn hashfull(&self) -> u32 {
+        let generation = self.generation.load(Ordering::Relaxed) & GENERATION_MASK;
+        let sample = &self.slots[..self.slots.len().min(1000)];
+        let used = sample
+            .iter()
+            .map(|[_, data]| data.load(Ordering::Relaxed))
+            .filter(|data| *data != 0 && ((*data >> 42) as u8) & GENERATION_MASK == generation)
+            .count();
+        (used * 1000 / sample.len()) as u32
+    }
+
+    fn index(&self, hash: u64) -> usize {
+        // the high bits of the hash spread evenly over any number of slots
+        ((hash as u128 * self.slots.len() as u128) >> 64) as usize
+    }
+}
+
+// mate scores are stored relative to the position, so they stay correct when it is reached at another ply
+fn score_to_table(score: i32, ply: i32) -> i32 {
+    if score > MATE_THRESHOLD {
+        score + ply
+    } else if score < -MATE_THRESHOLD {
+        score - ply
+    } else {
+        score
+    }
+}
+
+fn score_from_table(score: i32, ply: i32) -> i32 {
+    if score > MATE_THRESHOLD {
+        score - ply
+    } else if score < -MATE_THRESHOLD {
+        score + ply
+    } else {
+        score
+    }
+}
+
+const GENERATION_MASK: u8 = 0x3f;
+
+// layout of the data word: score (32 bits) | depth (8) | bound (2) | generation (6) | move (16)
+fn pack(entry: Entry, generation: u8) -> u64 {
+    let bound = match entry.bound {
+        Bound::Exact => 1,
+        Bound::Lower => 2,
+        Bound::Upper => 3,
+    };
+    (entry.score as u32 as u64)
+        | (entry.depth as u64) << 32
+        | (bound as u64) << 40
+        | (generation as u64) << 42
+        | (entry.best_move.map(pack_move).unwrap_or(0) as u64) << 48
+}
+
+fn unpack(data: u64) -> Entry {
+    Entry {
+        score: data as u32 as i32,
+        depth: (data >> 32) as u8,
+        bound: match (data >> 40) & 3 {
+            2 => Bound::Lower,
+            3 => Bound::Upper,
+            _ => Bound::Exact,
+        },
+        best_move: unpack_move((data >> 48) as u16),
+    }
+}
+
+// origin (6 bits) | destination (6) | promotion (3), a move from a8 to a8 stands for no move
+fn pack_move(mv: Move) -> u16 {
+    let square = |position: Position| (position.row * 8 + position.col) as u16;
+    let promotion = match mv.promotion {
+        None => 0,
+        Some(PieceVariant::Knight) => 1,
+        Some(PieceVariant::Bishop) => 2,
+        Some(PieceVariant::Rook) => 3,
+        Some(_) => 4,
+    };
+    square(mv.from) | square(mv.to) << 6 | promotion << 12
+}
+
+fn unpack_move(packed: u16) -> Option<Move> {
+    if packed == 0 {
+        return None;
+    }
+    let position = |square: u16| Position::new((square % 8) as i8, (square / 8) as i8);
+    let promotion = match packed >> 12 {
+        1 => Some(PieceVariant::Knight),
+        2 => Some(PieceVariant::Bishop),
+        3 => Some(PieceVariant::Rook),
+        4 => Some(PieceVariant::Queen),
+        _ => None,
+    };
+    Some(Move {
+        from: position(packed & 0x3f),
+        to: position((packed >> 6) & 0x3f),
+        promotion,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::MATE_SCORE;
+
+    fn square(name: &str) -> Position {
+        Position::from_square_name(name).unwrap()
+    }
+
+    #[test]
+    fn pack_round_trip() {
+        let moves = [
+            None,
+            Some(Move::new(square("g1"), square("f3"))),
+            Some(Move::new(square("h8"), square("a1"))),
+            Some(Move { promotion: Some(PieceVariant::Queen), ..Move::new(square("e7"), square("e8")) }),
+            Some(Move { promotion: Some(PieceVariant::Knight), ..Move::new(square("b2"), square("a1")) }),
+            Some(Move { promotion: Some(PieceVariant::Bishop), ..Move::new(square("c7"), square("d8")) }),
+            Some(Move { promotion: Some(PieceVariant::Rook), ..Move::new(square("h2"), square("h1")) }),
+        ];
+        let scores = [0, 1, -1, 35, -250, MATE_SCORE - 3, -(MATE_SCORE - 8), i32::MAX, i32::MIN];
+        for (i, best_move) in moves.into_iter().enumerate() {
+            for (j, score) in scores.into_iter().enumerate() {
+                let bound = [Bound::Exact, Bound::Lower, Bound::Upper][(i + j) % 3];
+                let entry = Entry { depth: (i * 9 + j) as u8, bound, score, best_move };
+                assert_eq!(unpack(pack(entry, GENERATION_MASK)), entry);
+                assert_eq!(unpack(pack(entry, 0)), entry);
+            }
+        }
+    }
+
+    #[test]
+    fn mate_scores_are_stored_relative_to_the_position() {
+        for score in [0, 42, -900, MATE_THRESHOLD, -MATE_THRESHOLD, MATE_SCORE - 5, -(MATE_SCORE - 12)] {
+            for ply in [0, 1, 7, 30] {
+                assert_eq!(score_from_table(score_to_table(score, ply), ply), score);
+            }
+        }
+        // a king captured 5 plies after the root, stored at ply 2, is 3 plies away from the stored position
+        // and 4 plies away when that position is reached at ply 1
+        let stored = score_to_table(MATE_SCORE - 5, 2);
+        assert_eq!(stored, MATE_SCORE - 3);
+        assert_eq!(score_from_table(stored, 1), MATE_SCORE - 4);
+        assert_eq!(score_from_table(score_to_table(-(MATE_SCORE - 5), 2), 1), -(MATE_SCORE - 4));
+        // other scores don't depend on the ply
+        assert_eq!(score_from_table(score_to_table(123, 9), 2), 123);
+    }
+
+    #[test]
+    fn store_and_probe() {
+        let tt = TranspositionTable::new(1);
+        let entry = Entry {
+            depth: 6,
+            bound: Bound::Lower,
+            score: MATE_SCORE - 9,
+            best_move: Some(Move::new(square("d1"), square("h5"))),
+        };
+        tt.store(0x1234_5678_9abc_def0, 4, entry);
+        assert_eq!(tt.probe(0x1234_5678_9abc_def0, 4), Some(entry));
+        assert_eq!(tt.probe(0x1234_5678_9abc_def0, 2).map(|entry| entry.score), Some(MATE_SCORE - 7));
+        assert_eq!(tt.probe(0x0fed_cba9_8765_4321, 4), None);
+        tt.clear();
+        assert_eq!(tt.probe(0x1234_5678_9abc_def0, 4), None);
+    }
+}
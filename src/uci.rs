@@ -0,0 +1,194 @@
+use crate::board::Move;
+use crate::engine::MATE_SCORE;
+use crate::game::Game;
+use crate::pieces::Color;
+use crate::player::{Action, Player};
+use crate::ui::Ui;
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+// the engine accepts a draw offer once its last search saw it behind by more than this
+const DRAW_ACCEPT_MARGIN: i32 = 200;
+
+/// an external chess engine speaking the Universal Chess Interface, running as a child process
+pub struct UciPlayer {
+    process: Child,
+    input: ChildStdin,
+    output: BufReader<ChildStdout>,
+    // the name the engine reported with `id name`
+    name: String,
+    // only used for games without a clock, otherwise the engine manages its time itself
+    depth: u8,
+    // the last score the engine reported for its own side in centipawns
+    score: Option<i32>,
+}
+
+impl UciPlayer {
+    /// spawns the engine, sets the given options and waits until it is ready for a new game
+    /// a path which is no file is split into the program and its arguments, e.g. `rusty-chess uci`
+    pub fn start(path: &str, options: &[(String, String)], depth: u8) -> io::Result<Self> {
+        let mut command = if Path::new(path).is_file() {
+            Command::new(path)
+        } else {
+            let mut words = path.split_whitespace();
+            let mut command = Command::new(words.next().unwrap_or(path));
+            command.args(words);
+            command
+        };
+        let mut process = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            // the engine must not write over the board
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| io::Error::new(e.kind(), format!("could not start the engine {path}: {e}")))?;
+        let input = process.stdin.take().expect("stdin is piped");
+        let output = BufReader::new(process.stdout.take().expect("stdout is piped"));
+        let mut player = UciPlayer {
+            process,
+            input,
+            output,
+            name: path.to_string(),
+            depth,
+            score: None,
+        };
+
+        player.send("uci")?;
+        let mut known_options = vec![];
+        loop {
+            let line = player.read_line()?;
+            match line.split_whitespace().next() {
+                Some("id") => {
+                    if let Some(name) = line.strip_prefix("id name ") {
+                        player.name = name.trim().to_string();
+                    }
+                }
+                Some("option") => known_options.push(option_name(&line).to_lowercase()),
+                Some("uciok") => break,
+                _ => (),
+            }
+        }
+        for (name, value) in options {
+            if !known_options.contains(&name.to_lowercase()) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("the engine {} has no option '{name}'", player.name),
+                ));
+            }
+            player.send(&format!("setoption name {name} value {value}"))?;
+        }
+        player.send("ucinewgame")?;
+        player.send("isready")?;
+        player.wait_for("readyok")?;
+        Ok(player)
+    }
+
+    fn send(&mut self, command: &str) -> io::Result<()> {
+        writeln!(self.input, "{command}")?;
+        self.input.flush()
+    }
+
+    fn read_line(&mut self) -> io::Result<String> {
+        let mut line = String::new();
+        if self.output.read_line(&mut line)? == 0 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "the engine has quit"));
+        }
+        Ok(line.trim().to_string())
+    }
+
+    /// reads the output of the engine up to the first line starting with the given token and returns that line
+    fn wait_for(&mut self, token: &str) -> io::Result<String> {
+        loop {
+            let line = self.read_line()?;
+            if line.split_whitespace().next() == Some(token) {
+                return Ok(line);
+            }
+        }
+    }
+
+    /// sends the current position, castling and en passant are not part of the rules, so the engine never plays them
+    fn best_move(&mut self, game: &Game) -> io::Result<Option<Move>> {
+        self.send(&format!("position fen {}", game.fen()))?;
+        let go = match &game.clock {
+            Some(clock) => format!(
+                "go wtime {} btime {} winc {} binc {}",
+                clock.remaining(Color::White).as_millis(),
+                clock.remaining(Color::Black).as_millis(),
+                clock.time_control.increment.as_millis(),
+                clock.time_control.increment.as_millis(),
+            ),
+            None => format!("go depth {}", self.depth),
+        };
+        self.send(&go)?;
+        loop {
+            let line = self.read_line()?;
+            let mut tokens = line.split_whitespace();
+            match tokens.next() {
+                Some("info") => {
+                    if let Some(score) = parse_score(&line) {
+                        self.score = Some(score);
+                    }
+                }
+                Some("bestmove") => return Ok(tokens.next().and_then(Move::from_uci)),
+                _ => (),
+            }
+        }
+    }
+}
+
+impl Player for UciPlayer {
+    fn choose(&mut self, game: &Game, _ui: &mut Ui) -> Action {
+        // positions with a capturable king are illegal in chess, so the engine is never asked about them
+        if let Some(mv) = game.board.king_capture(game.side_to_move) {
+            return Action::Move(mv);
+        }
+        // an engine without a move is check mate or stalemate, it has quit or has sent garbage
+        match self.best_move(game) {
+            Ok(Some(mv)) => Action::Move(mv),
+            _ => Action::Resign,
+        }
+    }
+
+    fn accept_draw(&mut self, _game: &Game, _ui: &mut Ui) -> bool {
+        self.score.is_some_and(|score| score < -DRAW_ACCEPT_MARGIN)
+    }
+
+    fn new_game(&mut self) {
+        self.score = None;
+        let _ = self.send("ucinewgame");
+        let _ = self.send("isready");
+        let _ = self.wait_for("readyok");
+    }
+
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+}
+
+impl Drop for UciPlayer {
+    fn drop(&mut self) {
+        let _ = self.send("quit");
+        let _ = self.process.wait();
+    }
+}
+
+/// the name of an option announced with `option name <name> type <type> ...`, names may contain spaces
+fn option_name(line: &str) -> &str {
+    let name = line.split_once(" name ").map(|(_, rest)| rest).unwrap_or_default();
+    name.split_once(" type ").map(|(name, _)| name).unwrap_or(name).trim()
+}
+
+/// the score of an `info` line in centipawns, a mate in n moves counts like capturing the king
+fn parse_score(line: &str) -> Option<i32> {
+    let mut tokens = line.split_whitespace().skip_while(|token| *token != "score").skip(1);
+    let kind = tokens.next()?;
+    let value: i32 = tokens.next()?.parse().ok()?;
+    match kind {
+        "cp" => Some(value),
+        "mate" if value > 0 => Some(MATE_SCORE - value),
+        "mate" => Some(-MATE_SCORE - value),
+        _ => None,
+    }
+}
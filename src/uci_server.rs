@@ -0,0 +1,299 @@
+use crate::board::Move;
+use crate::book::Book;
+use crate::config::Config;
+use crate::engine::{Engine, SearchResult, MATE_SCORE, MATE_THRESHOLD, MAX_DEPTH, MAX_HASH_MB, MAX_MULTI_PV, MAX_THREADS};
+use crate::eval::Breakdown;
+use crate::game::Game;
+use crate::pieces::Color;
+use crate::skill::{Skill, MAX_ELO, MAX_SKILL, MIN_ELO};
+use crate::syzygy::Tablebases;
+use crate::time_manager::TimeLimits;
+use crate::tt::TranspositionTable;
+
+use std::io::{self, BufRead};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+// the rating announced for `UCI_Elo` until the GUI sets one
+const DEFAULT_ELO: u32 = 1500;
+// how often a finished `go infinite` checks for `stop`
+const STOP_POLL: Duration = Duration::from_millis(10);
+
+/// the built-in engine speaking the Universal Chess Interface on stdin and stdout, started with `rusty-chess uci`
+/// so it can play in other programs, e.g. `rusty-chess match --engine2 "uci:rusty-chess uci"`
+struct UciServer {
+    game: Game,
+    depth: u8,
+    threads: usize,
+    multi_pv: usize,
+    // `Skill Level` and, if `UCI_LimitStrength` is set, `UCI_Elo`
+    skill_level: u8,
+    limit_strength: bool,
+    elo: u32,
+    tt: Arc<TranspositionTable>,
+    stop: Arc<AtomicBool>,
+    // the running search, it prints `bestmove` when it ends
+    search: Option<JoinHandle<()>>,
+    book: Option<Book>,
+    tablebases: Option<Arc<Tablebases>>,
+}
+
+pub fn run(config: &Config) -> io::Result<()> {
+    let book = config.book.as_deref().map(Book::open).transpose()?;
+    let tablebases = config.syzygy_path.as_deref().map(Tablebases::open).transpose()?.map(Arc::new);
+    let mut server = UciServer {
+        game: Game::new(None),
+        depth: config.engine_depth,
+        threads: config.engine_threads,
+        multi_pv: 1,
+        skill_level: config.engine_skill.level(),
+        limit_strength: false,
+        elo: DEFAULT_ELO,
+        tt: Arc::new(TranspositionTable::new(config.engine_hash)),
+        stop: Arc::new(AtomicBool::new(false)),
+        search: None,
+        book,
+        tablebases,
+    };
+    for line in io::stdin().lock().lines() {
+        let line = line?;
+        let mut tokens = line.split_whitespace();
+        match tokens.next() {
+            Some("uci") => {
+                println!("id name rusty-chess {}", env!("CARGO_PKG_VERSION"));
+                println!("option name Hash type spin default {} min 1 max {MAX_HASH_MB}", config.engine_hash);
+                println!("option name Threads type spin default {} min 1 max {MAX_THREADS}", config.engine_threads);
+                println!("option name MultiPV type spin default 1 min 1 max {MAX_MULTI_PV}");
+                println!("option name Skill Level type spin default {} min 1 max {MAX_SKILL}", config.engine_skill.level());
+                println!("option name UCI_LimitStrength type check default false");
+                println!("option name UCI_Elo type spin default {DEFAULT_ELO} min {MIN_ELO} max {MAX_ELO}");
+                let book = config.book.as_ref().map(|path| path.display().to_string());
+                println!("option name Book type string default {}", book.as_deref().unwrap_or("<empty>"));
+                let syzygy = config.syzygy_path.as_ref().map(|path| path.display().to_string());
+                println!("option name SyzygyPath type string default {}", syzygy.as_deref().unwrap_or("<empty>"));
+                println!("uciok");
+            }
+            Some("isready") => println!("readyok"),
+            Some("setoption") => server.set_option(&line),
+            Some("ucinewgame") => {
+                server.stop_search();
+                server.tt.clear();
+            }
+            Some("position") => {
+                server.stop_search();
+                server.set_position(tokens.collect());
+            }
+            Some("go") => {
+                server.stop_search();
+                server.go(tokens.collect());
+            }
+            Some("stop") => server.stop_search(),
+            // not part of the protocol, prints the evaluation of the current position
+            Some("eval") => print!("{}", Breakdown::of(&server.game.board).table()),
+            Some("quit") => break,
+            _ => (),
+        }
+    }
+    server.stop_search();
+    Ok(())
+}
+
+impl UciServer {
+    /// `setoption name Hash value 64`, unknown options are ignored like the protocol demands
+    fn set_option(&mut self, line: &str) {
+        let Some((name, value)) = line.split_once(" name ").and_then(|(_, rest)| rest.split_once(" value ")) else {
+            return;
+        };
+        let value = value.trim();
+        match name.trim().to_lowercase().as_str() {
+            "hash" => {
+                if let Ok(megabytes) = value.parse::<usize>() {
+                    self.stop_search();
+                    self.tt = Arc::new(TranspositionTable::new(megabytes.clamp(1, MAX_HASH_MB)));
+                }
+            }
+            "threads" => {
+                if let Ok(threads) = value.parse::<usize>() {
+                    self.threads = threads.clamp(1, MAX_THREADS);
+                }
+            }
+            "multipv" => {
+                if let Ok(lines) = value.parse::<usize>() {
+                    self.multi_pv = lines.clamp(1, MAX_MULTI_PV);
+                }
+            }
+            "skill level" => {
+                if let Ok(level) = value.parse() {
+                    self.skill_level = level;
+                }
+            }
+            "uci_limitstrength" => self.limit_strength = value.eq_ignore_ascii_case("true"),
+            "uci_elo" => {
+                if let Ok(elo) = value.parse() {
+                    self.elo = elo;
+                }
+            }
+            "book" => {
+                self.book = match value {
+                    "" | "<empty>" => None,
+                    path => match Book::open(Path::new(path)) {
+                        Ok(book) => Some(book),
+                        Err(e) => {
+                            println!("info string {e}");
+                            None
+                        }
+                    },
+                }
+            }
+            "syzygypath" => {
+                self.tablebases = match value {
+                    "" | "<empty>" => None,
+                    path => match Tablebases::open(Path::new(path)) {
+                        Ok(tablebases) => {
+                            println!(
+                                "info string found {} Syzygy tables with up to {} pieces",
+                                tablebases.count(),
+                                tablebases.max_pieces()
+                            );
+                            Some(Arc::new(tablebases))
+                        }
+                        Err(e) => {
+                            println!("info string {e}");
+                            None
+                        }
+                    },
+                }
+            }
+            _ => (),
+        }
+    }
+
+    /// `position startpos moves e2e4 e7e5` or `position fen <fen> moves ...`, moves break off at the first illegal one
+    fn set_position(&mut self, tokens: Vec<&str>) {
+        let moves_at = tokens.iter().position(|token| *token == "moves").unwrap_or(tokens.len());
+        let game = match tokens.first() {
+            Some(&"startpos") => Ok(Game::new(None)),
+            Some(&"fen") => Game::from_fen(&tokens[1..moves_at].join(" "), None),
+            _ => return,
+        };
+        let Ok(mut game) = game else {
+            return;
+        };
+        for token in tokens.iter().skip(moves_at + 1) {
+            match Move::from_uci(token) {
+                Some(mv) if game.board.moves(game.side_to_move).contains(&mv) => {
+                    let _ = game.play(mv);
+                }
+                _ => break,
+            }
+        }
+        self.game = game;
+    }
+
+    /// starts a search on its own thread, so `stop` can be read while it runs
+    fn go(&mut self, tokens: Vec<&str>) {
+        let mut depth = None;
+        let mut infinite = false;
+        let mut movetime = None;
+        // remaining time and increment of white and black in milliseconds
+        let mut time = [None; 2];
+        let mut increment = [0; 2];
+        let mut moves_to_go = None;
+        let mut tokens = tokens.into_iter();
+        while let Some(token) = tokens.next() {
+            let mut number = || tokens.next().and_then(|n| n.parse::<u64>().ok());
+            match token {
+                "depth" => depth = number().map(|d| d.clamp(1, MAX_DEPTH as u64) as u8),
+                "infinite" => infinite = true,
+                "movetime" => movetime = number(),
+                "wtime" => time[0] = number(),
+                "btime" => time[1] = number(),
+                "winc" => increment[0] = number().unwrap_or(0),
+                "binc" => increment[1] = number().unwrap_or(0),
+                "movestogo" => moves_to_go = number().map(|n| n as u32),
+                _ => (),
+            }
+        }
+        let side = if self.game.side_to_move == Color::White { 0 } else { 1 };
+        let limits = match (movetime, time[side]) {
+            _ if infinite || depth.is_some() => None,
+            (Some(movetime), _) => Some(TimeLimits::fixed(Duration::from_millis(movetime))),
+            (None, Some(time)) => Some(TimeLimits::new(
+                Duration::from_millis(time),
+                Duration::from_millis(increment[side]),
+                moves_to_go,
+            )),
+            (None, None) => None,
+        };
+        let depth = if infinite { MAX_DEPTH } else { depth.unwrap_or(self.depth) };
+        // `go infinite` is analysis, a book move would end it at once
+        if !infinite {
+            if let Some(mv) = self.book.as_mut().and_then(|book| book.pick(&self.game)) {
+                println!("info string book move");
+                println!("bestmove {}", mv.to_uci());
+                return;
+            }
+        }
+        self.stop.store(false, Ordering::Relaxed);
+        let mut engine = Engine::with_table(depth, self.tt.clone(), self.stop.clone());
+        engine.threads = self.threads;
+        engine.multi_pv = self.multi_pv;
+        engine.tablebases = self.tablebases.clone();
+        engine.skill = if self.limit_strength { Skill::from_elo(self.elo) } else { Skill::new(self.skill_level) };
+        let tt = self.tt.clone();
+        let board = self.game.board.clone();
+        let color = self.game.side_to_move;
+        let stop = self.stop.clone();
+        self.search = Some(thread::spawn(move || {
+            let start = Instant::now();
+            let result = engine.search_with_info(&board, color, limits, &mut |result| print_info(result, start, &tt));
+            // the protocol allows `bestmove` after `go infinite` only once `stop` has been sent, even if the search
+            // has reached its depth long before
+            while infinite && !stop.load(Ordering::Relaxed) {
+                thread::sleep(STOP_POLL);
+            }
+            match result.best_move {
+                Some(mv) => println!("bestmove {}", mv.to_uci()),
+                None => println!("bestmove 0000"),
+            }
+        }));
+    }
+
+    fn stop_search(&mut self) {
+        if let Some(search) = self.search.take() {
+            self.stop.store(true, Ordering::Relaxed);
+            let _ = search.join();
+        }
+    }
+}
+
+/// one info line per line of the search, numbered with `multipv` if there is more than one
+fn print_info(result: &SearchResult, start: Instant, tt: &TranspositionTable) {
+    for (i, line) in result.lines.iter().enumerate() {
+        let multi_pv = if result.lines.len() > 1 { format!(" multipv {}", i + 1) } else { String::new() };
+        let pv: Vec<String> = line.pv.iter().map(|mv| mv.to_uci()).collect();
+        println!(
+            "info depth {}{multi_pv} score {} nodes {} time {} hashfull {} pv {}",
+            result.depth,
+            uci_score(line.score),
+            result.nodes,
+            start.elapsed().as_millis(),
+            tt.hashfull(),
+            pv.join(" ")
+        );
+    }
+}
+
+/// a score in centipawns or, once the search sees a king captured, the number of moves until then
+fn uci_score(score: i32) -> String {
+    if score > MATE_THRESHOLD {
+        format!("mate {}", (MATE_SCORE - score + 1) / 2)
+    } else if score < -MATE_THRESHOLD {
+        format!("mate -{}", (MATE_SCORE + score + 1) / 2)
+    } else {
+        format!("cp {score}")
+    }
+}
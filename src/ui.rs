@@ -0,0 +1,106 @@
+use crate::analysis::AnalysisView;
+use crate::board::{Board, Move};
+use crate::clock::Clock;
+use crate::console::DisplaySettings;
+use crate::pieces::{Color, Position};
+use crate::renderer::Renderer;
+use crate::theme::ColorDepth;
+use crate::CursorPosition;
+
+/// the screen all players share: the renderer, the cursor of the local player and the status lines
+pub struct Ui {
+    pub renderer: Box<dyn Renderer>,
+    pub settings: DisplaySettings,
+    // needed to reduce the next theme to the colors of the terminal
+    pub color_depth: ColorDepth,
+    pub cursor_pos: CursorPosition,
+    pub move_set: Vec<Position>,
+    /// a move suggested to the local player, its squares are highlighted until it is cleared
+    pub hint: Option<Move>,
+    clock: Option<String>,
+    message: Option<String>,
+    analysis: Option<AnalysisView>,
+}
+
+impl Ui {
+    pub fn new(renderer: Box<dyn Renderer>, settings: DisplaySettings, color_depth: ColorDepth) -> Self {
+        Ui {
+            renderer,
+            settings,
+            color_depth,
+            cursor_pos: CursorPosition::new(),
+            move_set: vec![],
+            hint: None,
+            clock: None,
+            message: None,
+            analysis: None,
+        }
+    }
+
+    /// draws everything from scratch
+    pub fn init(&mut self, board: &Board) {
+        let _ = self.renderer.init(board, &self.cursor_pos.get_position(), &self.move_set, self.hint, &self.settings);
+        self.draw_status();
+        if self.analysis.is_some() {
+            self.draw_analysis();
+        }
+    }
+
+    /// redraws the board after a move of the cursor or a piece
+    pub fn redraw(&mut self, board: &Board) {
+        let _ = self.renderer.board(board, &self.cursor_pos.get_position(), &self.move_set, self.hint, &self.settings);
+    }
+
+    /// turns the board towards a player, the labels of the border are only drawn by init
+    pub fn orient_towards(&mut self, color: Color, board: &Board) {
+        let flipped = color == Color::Black;
+        if self.settings.flipped != flipped {
+            self.settings.flipped = flipped;
+            self.init(board);
+        }
+    }
+
+    /// shows a message below the board until it is replaced, None clears it
+    pub fn set_message(&mut self, message: Option<String>) {
+        if self.message != message {
+            self.message = message;
+            self.draw_status();
+        }
+    }
+
+    /// shows the remaining time of both players, only redrawn when the displayed time changes
+    pub fn update_clock(&mut self, clock: &Clock) {
+        let text = [Color::White, Color::Black]
+            .map(|color| format!("{color} {}", clock.format(color)))
+            .join("   ");
+        self.set_header(Some(text));
+    }
+
+    /// shows a line above the board in place of the clock, e.g. the puzzle being solved
+    pub fn set_header(&mut self, text: Option<String>) {
+        if self.clock != text {
+            self.clock = text;
+            self.draw_status();
+        }
+    }
+
+    /// shows the evaluation bar and the best lines of the analysis beside the board, None hides them
+    pub fn set_analysis(&mut self, analysis: Option<AnalysisView>) {
+        if self.analysis != analysis {
+            self.analysis = analysis;
+            self.draw_analysis();
+        }
+    }
+
+    pub fn game_over(&mut self, result: &str) {
+        let _ = self.renderer.game_over(result);
+    }
+
+    fn draw_status(&mut self) {
+        let _ = self.renderer.status(self.clock.as_deref(), self.message.as_deref(), &self.settings);
+    }
+
+    fn draw_analysis(&mut self) {
+        let _ = self.renderer.analysis(self.analysis.as_ref(), &self.settings);
+    }
+}
@@ -1,5 +1,10 @@
-use crossterm::event::{read, Event, KeyCode, KeyEventKind};
+use crossterm::event::{poll, read, Event, KeyCode, KeyEventKind, KeyModifiers, MouseButton, MouseEventKind};
 
+use crate::pieces::Position;
+
+use std::time::Duration;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
 pub enum KeyPress {
     Left,
     Right,
@@ -8,25 +13,267 @@ pub enum KeyPress {
     Enter,
     Esc,
     BackSpace,
+    Flip,
+    Theme,
+    /// the next two typed characters name the square the cursor jumps to, e.g. `e4`
+    Goto,
+    Resign,
+    OfferDraw,
+    /// asks the engine for a move and highlights it
+    Hint,
+    /// goes back and forth through the moves in the analysis
+    StepBack,
+    StepForward,
+    /// changes the piece on the square under the cursor in the analysis
+    EditSquare,
+    /// hands the move to the other side in the analysis
+    SwitchSide,
+    // mouse events carry the terminal cell (column, row) they happened on
+    MouseDown(u16, u16),
+    MouseDrag(u16, u16),
+    MouseUp(u16, u16),
+    /// the terminal has been resized and everything has to be redrawn
+    Resize,
+}
+
+impl KeyPress {
+    /// mirrors the arrow keys, so they follow the screen directions of a flipped board
+    pub fn oriented(self, flipped: bool) -> KeyPress {
+        match (self, flipped) {
+            (KeyPress::Left, true) => KeyPress::Right,
+            (KeyPress::Right, true) => KeyPress::Left,
+            (KeyPress::Up, true) => KeyPress::Down,
+            (KeyPress::Down, true) => KeyPress::Up,
+            (key, _) => key,
+        }
+    }
+
+    /// the name of an action in the config file
+    fn from_name(name: &str) -> Option<KeyPress> {
+        match name {
+            "left" => Some(KeyPress::Left),
+            "right" => Some(KeyPress::Right),
+            "up" => Some(KeyPress::Up),
+            "down" => Some(KeyPress::Down),
+            "select" => Some(KeyPress::Enter),
+            "quit" => Some(KeyPress::Esc),
+            "deselect" => Some(KeyPress::BackSpace),
+            "flip" => Some(KeyPress::Flip),
+            "theme" => Some(KeyPress::Theme),
+            "goto" => Some(KeyPress::Goto),
+            "resign" => Some(KeyPress::Resign),
+            "draw" => Some(KeyPress::OfferDraw),
+            "hint" => Some(KeyPress::Hint),
+            "back" => Some(KeyPress::StepBack),
+            "forward" => Some(KeyPress::StepForward),
+            "edit" => Some(KeyPress::EditSquare),
+            "side" => Some(KeyPress::SwitchSide),
+            _ => None,
+        }
+    }
+}
+
+/// maps the keys of the keyboard onto the actions of the game
+#[derive(Clone, PartialEq, Debug)]
+pub struct KeyBindings {
+    bindings: Vec<(KeyCode, KeyPress)>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        use KeyCode::*;
+        KeyBindings {
+            bindings: vec![
+                (Left, KeyPress::Left),
+                (Char('h'), KeyPress::Left),
+                (Char('a'), KeyPress::Left),
+                (Down, KeyPress::Down),
+                (Char('j'), KeyPress::Down),
+                (Char('s'), KeyPress::Down),
+                (Up, KeyPress::Up),
+                (Char('k'), KeyPress::Up),
+                (Char('w'), KeyPress::Up),
+                (Right, KeyPress::Right),
+                (Char('l'), KeyPress::Right),
+                (Char('d'), KeyPress::Right),
+                (Enter, KeyPress::Enter),
+                (Char(' '), KeyPress::Enter),
+                (Esc, KeyPress::Esc),
+                (Char('q'), KeyPress::Esc),
+                (Backspace, KeyPress::BackSpace),
+                (Char('f'), KeyPress::Flip),
+                (Char('t'), KeyPress::Theme),
+                (Char(':'), KeyPress::Goto),
+                (Char('r'), KeyPress::Resign),
+                (Char('o'), KeyPress::OfferDraw),
+                (Char('?'), KeyPress::Hint),
+                (Char('i'), KeyPress::Hint),
+                (Char('b'), KeyPress::StepBack),
+                (Char('n'), KeyPress::StepForward),
+                (Char('e'), KeyPress::EditSquare),
+                (Char('c'), KeyPress::SwitchSide),
+            ],
+        }
+    }
+}
+
+impl KeyBindings {
+    pub fn action(&self, code: KeyCode) -> Option<KeyPress> {
+        let code = match code {
+            KeyCode::Char(c) => KeyCode::Char(c.to_ascii_lowercase()),
+            code => code,
+        };
+        self.bindings.iter().find(|(k, _)| *k == code).map(|(_, action)| *action)
+    }
+
+    /// replaces the keys of an action with a comma separated list of key names, e.g. `Left, h, a`
+    pub fn set(&mut self, action_name: &str, keys: &str) -> Result<(), String> {
+        let action = KeyPress::from_name(action_name).ok_or(format!("unknown action '{action_name}'"))?;
+        let codes = keys
+            .split(',')
+            .map(|name| parse_key(name.trim()).ok_or(format!("unknown key '{}'", name.trim())))
+            .collect::<Result<Vec<KeyCode>, String>>()?;
+        self.bindings.retain(|(k, a)| *a != action && !codes.contains(k));
+        self.bindings.extend(codes.into_iter().map(|code| (code, action)));
+        Ok(())
+    }
+
+    /// lists the keys bound to an action, e.g. `ENTER/SPACE`
+    pub fn describe(&self, action: KeyPress) -> String {
+        self.keys(action).into_iter().map(key_name).collect::<Vec<_>>().join("/")
+    }
+
+    /// lists the keys of the four directions side by side, e.g. `←↓↑→/hjkl/asdw`
+    pub fn describe_movement(&self) -> String {
+        let directions = [KeyPress::Left, KeyPress::Down, KeyPress::Up, KeyPress::Right].map(|d| self.keys(d));
+        let sets = directions.iter().map(Vec::len).max().unwrap_or(0);
+        (0..sets)
+            .map(|i| {
+                directions
+                    .iter()
+                    .map(|keys| keys.get(i).map(|k| key_name(*k)).unwrap_or_else(|| "-".to_string()))
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    fn keys(&self, action: KeyPress) -> Vec<KeyCode> {
+        self.bindings.iter().filter(|(_, a)| *a == action).map(|(k, _)| *k).collect()
+    }
+}
+
+fn parse_key(name: &str) -> Option<KeyCode> {
+    match name.to_lowercase().as_str() {
+        "left" => Some(KeyCode::Left),
+        "right" => Some(KeyCode::Right),
+        "up" => Some(KeyCode::Up),
+        "down" => Some(KeyCode::Down),
+        "enter" => Some(KeyCode::Enter),
+        "esc" => Some(KeyCode::Esc),
+        "backspace" => Some(KeyCode::Backspace),
+        "tab" => Some(KeyCode::Tab),
+        "space" => Some(KeyCode::Char(' ')),
+        _ => {
+            let mut chars = name.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Some(KeyCode::Char(c.to_ascii_lowercase())),
+                _ => None,
+            }
+        }
+    }
+}
+
+fn key_name(code: KeyCode) -> String {
+    match code {
+        KeyCode::Left => "←".to_string(),
+        KeyCode::Right => "→".to_string(),
+        KeyCode::Up => "↑".to_string(),
+        KeyCode::Down => "↓".to_string(),
+        KeyCode::Enter => "ENTER".to_string(),
+        KeyCode::Esc => "ESC".to_string(),
+        KeyCode::Backspace => "BACKSPACE".to_string(),
+        KeyCode::Tab => "TAB".to_string(),
+        KeyCode::Char(' ') => "SPACE".to_string(),
+        KeyCode::Char(c) => c.to_string(),
+        _ => "?".to_string(),
+    }
 }
 
 /// function to get user input
 /// blocks the application until user action
-pub(crate) fn await_user_input() -> Option<KeyPress> {
+pub(crate) fn await_user_input(bindings: &KeyBindings) -> Option<KeyPress> {
     match read().unwrap() {
         Event::Key(event) => match event.kind {
-            KeyEventKind::Press => match event.code {
-                KeyCode::Up => Some(KeyPress::Up),
-                KeyCode::Down => Some(KeyPress::Down),
-                KeyCode::Left => Some(KeyPress::Left),
-                KeyCode::Right => Some(KeyPress::Right),
-                KeyCode::Enter => Some(KeyPress::Enter),
-                KeyCode::Esc => Some(KeyPress::Esc),
-                KeyCode::Backspace => Some(KeyPress::BackSpace),
-                _ => None,
-            },
+            // in raw mode ctrl-c is no signal but a key press, it always quits the game
+            KeyEventKind::Press if is_ctrl_c(event.code, event.modifiers) => Some(KeyPress::Esc),
+            KeyEventKind::Press => bindings.action(event.code),
+            _ => None,
+        },
+        Event::Mouse(event) => match event.kind {
+            MouseEventKind::Down(MouseButton::Left) => Some(KeyPress::MouseDown(event.column, event.row)),
+            MouseEventKind::Drag(MouseButton::Left) => Some(KeyPress::MouseDrag(event.column, event.row)),
+            MouseEventKind::Up(MouseButton::Left) => Some(KeyPress::MouseUp(event.column, event.row)),
             _ => None,
         },
+        Event::Resize(_, _) => Some(KeyPress::Resize),
         _ => None,
     }
 }
+
+/// like await_user_input, but gives up after the timeout, so a running clock can be redrawn in between
+pub(crate) fn poll_user_input(bindings: &KeyBindings, timeout: Duration) -> Option<KeyPress> {
+    if poll(timeout).unwrap_or(false) {
+        await_user_input(bindings)
+    } else {
+        None
+    }
+}
+
+/// reads the answer to a yes/no question, `y` or `j` for yes, any other key for no
+pub(crate) fn await_confirmation() -> bool {
+    while let Ok(event) = read() {
+        if let Event::Key(key) = event {
+            if key.kind == KeyEventKind::Press {
+                return matches!(key.code, KeyCode::Char('y' | 'Y' | 'j' | 'J'));
+            }
+        }
+    }
+    false
+}
+
+/// reads a square name like `e4` typed by the user
+/// returns None if the input is cancelled with ESC or is no valid square, and ESC as error for ctrl-c, which quits
+/// the game like everywhere else
+pub(crate) fn await_square_name() -> Result<Option<Position>, KeyPress> {
+    let mut name = String::new();
+    while name.len() < 2 {
+        let Ok(event) = read() else {
+            return Ok(None);
+        };
+        if let Event::Key(event) = event {
+            match (event.kind, event.code) {
+                (KeyEventKind::Press, code) if is_ctrl_c(code, event.modifiers) => return Err(KeyPress::Esc),
+                (KeyEventKind::Press, KeyCode::Char(c)) => name.push(c),
+                (KeyEventKind::Press, KeyCode::Esc) => return Ok(None),
+                _ => (),
+            }
+        }
+    }
+    Ok(Position::from_square_name(&name))
+}
+
+/// blocks until any key is pressed
+pub(crate) fn await_any_key() {
+    while let Ok(event) = read() {
+        if let Event::Key(key) = event {
+            if key.kind == KeyEventKind::Press {
+                return;
+            }
+        }
+    }
+}
+
+fn is_ctrl_c(code: KeyCode, modifiers: KeyModifiers) -> bool {
+    modifiers.contains(KeyModifiers::CONTROL) && matches!(code, KeyCode::Char('c') | KeyCode::Char('C'))
+}
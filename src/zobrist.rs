@@ -0,0 +1,106 @@
+use crate::board::{Board, Move};
+use crate::pieces::{Color, Piece, PieceVariant, Position};
+
+use std::sync::OnceLock;
+
+// one key per piece kind, color and square, followed by the key of black to move
+static KEYS: OnceLock<[u64; 12 * 64 + 1]> = OnceLock::new();
+
+fn keys() -> &'static [u64; 12 * 64 + 1] {
+    KEYS.get_or_init(|| {
+        // splitmix64 with a fixed seed, the keys are the same in every run
+        let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut keys = [0; 12 * 64 + 1];
+        for key in keys.iter_mut() {
+            state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+            let mut z = state;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            *key = z ^ (z >> 31);
+        }
+        keys
+    })
+}
+
+/// the key of a piece standing on a square
+pub fn piece_key(variant: PieceVariant, color: Color, position: Position) -> u64 {
+    let kind = match variant {
+        PieceVariant::Pawn => 0,
+        PieceVariant::Knight => 1,
+        PieceVariant::Bishop => 2,
+        PieceVariant::Rook => 3,
+        PieceVariant::Queen => 4,
+        PieceVariant::King => 5,
+    } + if color == Color::Black { 6 } else { 0 };
+    keys()[kind * 64 + (position.row * 8 + position.col) as usize]
+}
+
+/// toggled whenever the side to move changes
+pub fn side_key() -> u64 {
+    keys()[12 * 64]
+}
+
+/// the Zobrist hash of a position, equal positions with the same side to move have equal hashes
+pub fn hash(board: &Board, side_to_move: Color) -> u64 {
+    let pieces = board.board_array.iter().flatten().flatten();
+    let hash = pieces.fold(0, |hash, p| hash ^ piece_key(p.variant, p.color, p.position));
+    if side_to_move == Color::Black {
+        hash ^ side_key()
+    } else {
+        hash
+    }
+}
+
+/// the change of the hash by a move of the given piece capturing the given piece, xor it onto the hash before the move
+pub fn move_delta(piece: Piece, mv: Move, captured: Option<Piece>) -> u64 {
+    let mut delta = side_key() ^ piece_key(piece.variant, piece.color, mv.from);
+    delta ^= piece_key(mv.promotion.unwrap_or(piece.variant), piece.color, mv.to);
+    if let Some(captured) = captured {
+        delta ^= piece_key(captured.variant, captured.color, mv.to);
+    }
+    delta
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Game;
+
+    /// plays the move with make_move and checks the updated hash against the hash of the new board
+    fn assert_delta(fen: &str, uci: &str) {
+        let game = Game::from_fen(fen, None).unwrap();
+        let mut board = game.board.clone();
+        let mv = Move::from_uci(uci).unwrap();
+        assert!(board.moves(game.side_to_move).contains(&mv), "{uci} in {fen}");
+        let piece = board[mv.from].unwrap();
+        let before = hash(&board, game.side_to_move);
+        let captured = board.make_move(mv);
+        assert_eq!(before ^ move_delta(piece, mv, captured), hash(&board, game.side_to_move.opponent()), "{uci} in {fen}");
+    }
+
+    #[test]
+    fn quiet_move() {
+        assert_delta("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1", "g1f3");
+    }
+
+    #[test]
+    fn capture() {
+        assert_delta("rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w - - 0 2", "e4d5");
+        assert_delta("rnbqkbnr/ppp1pppp/8/3P4/8/8/PPPP1PPP/RNBQKBNR b - - 0 2", "d8d5");
+    }
+
+    #[test]
+    fn promotion() {
+        assert_delta("8/4P1k1/8/8/8/8/8/4K3 w - - 0 1", "e7e8q");
+        assert_delta("8/4P1k1/8/8/8/8/8/4K3 w - - 0 1", "e7e8n");
+        // a capture and a promotion in one move
+        assert_delta("3r2k1/4P3/8/8/8/8/8/4K3 w - - 0 1", "e7d8r");
+        assert_delta("4k3/8/8/8/8/8/1p6/R3K3 b - - 0 1", "b2a1q");
+    }
+
+    #[test]
+    fn side_to_move() {
+        let game = Game::new(None);
+        assert_eq!(hash(&game.board, Color::White) ^ side_key(), hash(&game.board, Color::Black));
+    }
+}